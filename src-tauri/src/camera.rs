@@ -1,48 +1,306 @@
+use crate::error::AppError;
+use crate::exif::{add_exif_to_jpeg, PhotoMetadata};
+use crate::gallery::{ensure_camera_dir, new_gallery_image, GalleryImage};
+use crate::media::unique_path;
+use crate::retention::enforce_retention;
+use crate::sound_trigger::stop_sound_triggered_capture;
+use ab_glyph::{FontRef, PxScale};
 use base64::{engine::general_purpose::STANDARD, Engine};
 use chrono::Local;
-use image::{ImageBuffer, Rgb};
+use image::{
+    codecs::gif::{GifEncoder, Repeat},
+    imageops::FilterType,
+    Delay, DynamicImage, Frame, GrayImage, ImageBuffer, ImageFormat, ImageReader, Luma, Rgb,
+    RgbImage, Rgba, RgbaImage,
+};
+use imageproc::drawing::{draw_text_mut, text_size};
 use nokhwa::{
-    pixel_format::RgbFormat,
-    utils::{CameraFormat, CameraIndex, FrameFormat, RequestedFormat, RequestedFormatType, Resolution},
-    Camera,
+    native_api_backend,
+    pixel_format::{LumaFormat, RgbFormat, YuyvFormat},
+    utils::{
+        all_known_camera_controls, yuyv422_to_rgb, ApiBackend, CameraFormat, CameraIndex,
+        ControlValueDescription, ControlValueSetter, FrameFormat, KnownCameraControl,
+        RequestedFormat, RequestedFormatType, Resolution,
+    },
+    Buffer, Camera,
 };
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock};
 use std::{
-    io::Cursor,
+    collections::VecDeque,
+    fs,
+    io::{Cursor, ErrorKind, Write},
+    path::{Path, PathBuf},
     sync::{
         atomic::{AtomicBool, Ordering},
+        mpsc::{channel, Receiver, RecvTimeoutError, Sender},
         Arc,
     },
     thread,
-    time::Duration,
+    time::{Duration, Instant},
 };
 use tauri::{AppHandle, Emitter};
 
 // Global camera state
 static CAMERA_RUNNING: AtomicBool = AtomicBool::new(false);
 static STOP_SIGNAL: AtomicBool = AtomicBool::new(false);
+static TIMELAPSE_RUNNING: AtomicBool = AtomicBool::new(false);
+static STOP_TIMELAPSE: AtomicBool = AtomicBool::new(false);
+static PNG_SEQUENCE_RUNNING: AtomicBool = AtomicBool::new(false);
+static STOP_PNG_SEQUENCE: AtomicBool = AtomicBool::new(false);
+static RINGBUFFER_RUNNING: AtomicBool = AtomicBool::new(false);
+static STOP_RINGBUFFER: AtomicBool = AtomicBool::new(false);
+static CAPTURE_TIMER_RUNNING: AtomicBool = AtomicBool::new(false);
+static CANCEL_CAPTURE: AtomicBool = AtomicBool::new(false);
+// Stops every background task that reads the stream (timelapse, PNG sequence, ring buffer,
+// self-timer)
+static STOP_CAMERA_TASKS: AtomicBool = AtomicBool::new(false);
+// Pause requested by the app, and whether the stream thread has actually paused
+static PAUSE_REQUESTED: AtomicBool = AtomicBool::new(false);
+static CAMERA_PAUSED: AtomicBool = AtomicBool::new(false);
+// Set when the camera has no usable white balance control; photos are corrected in software
+static SOFTWARE_WHITE_BALANCE: AtomicBool = AtomicBool::new(false);
+// Set while the stream delivers single-channel infrared frames instead of colour
+static INFRARED_STREAM: AtomicBool = AtomicBool::new(false);
 
-// Shared frame buffer for capture (stores JPEG bytes ready to save)
+// Shared frame buffers for capture: JPEG bytes ready to save, plus the
+// decoded RGB pixels for lossless captures and image analysis. In YUYV
+// passthrough mode only the raw frame is kept and converted on demand.
 lazy_static::lazy_static! {
     static ref LATEST_FRAME: Arc<RwLock<Option<Vec<u8>>>> = Arc::new(RwLock::new(None));
+    static ref LATEST_RGB_FRAME: Arc<RwLock<Option<RgbImage>>> = Arc::new(RwLock::new(None));
+    static ref LATEST_YUYV_FRAME: Arc<RwLock<Option<YuyvFrame>>> = Arc::new(RwLock::new(None));
+    // Format negotiated with the camera when the stream opened
+    static ref CAMERA_FORMAT: RwLock<Option<CameraFormatInfo>> = RwLock::new(None);
+    // Downscaled grayscale frame from the previous detect_motion call
+    static ref MOTION_REFERENCE: RwLock<Option<GrayImage>> = RwLock::new(None);
+    // Most recent photo/frame capture, kept after the stream stops so reviews can reopen it
+    static ref LAST_CAPTURE: RwLock<Option<CameraFrame>> = RwLock::new(None);
+    // Recent preview frame outcomes, updated by the stream thread
+    static ref PREVIEW_COUNTERS: RwLock<PreviewCounters> = RwLock::new(PreviewCounters::default());
+    // White balance change waiting for the stream thread, which owns the camera
+    static ref WHITE_BALANCE_REQUEST: Mutex<Option<WhiteBalanceRequest>> = Mutex::new(None);
+    // Exposure bracket waiting for the stream thread, and where to send the frames
+    static ref HDR_REQUEST: Mutex<Option<HdrRequest>> = Mutex::new(None);
+    // Frame timing run waiting for the stream thread
+    static ref LATENCY_REQUEST: Mutex<Option<LatencyRequest>> = Mutex::new(None);
+    // Control range query waiting for the stream thread
+    static ref CONTROL_RANGES_REQUEST: Mutex<Option<Sender<Vec<CameraControlRange>>>> =
+        Mutex::new(None);
+    // Most recent frames for pre-event capture, oldest first; kept after the ring stops
+    static ref FRAME_RING: Mutex<VecDeque<Arc<RgbImage>>> = Mutex::new(VecDeque::new());
+    // Preview rate, size and quality, read by the stream thread every frame
+    static ref PREVIEW_CONFIG: RwLock<PreviewConfig> = RwLock::new(load_preview_config());
 }
 
-// Single resolution for everything
+// Default stream resolution
 const CAMERA_WIDTH: u32 = 640;
 const CAMERA_HEIGHT: u32 = 480;
 
+// Preview statistics cover this much recent history
+const PREVIEW_STATS_WINDOW: Duration = Duration::from_secs(5);
+
+// Most frames the pre-event ring buffer may hold (about 230 MB at 640x480)
+const MAX_RINGBUFFER_FRAMES: u32 = 750;
+
+// Standard modes tried, largest first, when the requested one won't stream; the ones
+// below the default keep a fallback for cameras that can't manage the default either
+const FALLBACK_RESOLUTIONS: [(u32, u32); 5] = [
+    (1920, 1080),
+    (1280, 720),
+    (640, 480),
+    (320, 240),
+    (160, 120),
+];
+
 // JPEG quality
-const JPEG_QUALITY: u8 = 85;
+pub(crate) const JPEG_QUALITY: u8 = 85;
 
 // Target FPS for streaming
 const TARGET_FPS: u64 = 25;
 
+// Longest GIF burst, the size frames are reduced to (longer side) to keep the file small,
+// and the encoder's speed (1-30; higher is faster but with poorer colours)
+const MAX_GIF_FRAMES: u32 = 100;
+const GIF_MAX_SIZE: u32 = 320;
+const GIF_ENCODE_SPEED: i32 = 10;
+
+// Highest preview rate and smallest preview scale set_preview_config accepts
+const MAX_PREVIEW_FPS: u32 = 60;
+const MIN_PREVIEW_SCALE: f32 = 0.1;
+
+// Default pause between camera open attempts
+const DEFAULT_OPEN_RETRY_DELAY_MS: u64 = 1000;
+
+// Shortest allowed timelapse interval
+const MIN_TIMELAPSE_INTERVAL_MS: u64 = 100;
+
+// Width frames are reduced to before comparing them for motion
+const MOTION_SAMPLE_WIDTH: u32 = 160;
+
+// Region analysis: Sobel magnitude (0-255 scale) that counts as an edge, and the
+// brightness/colourfulness limits for a pixel to count as paper
+const ROI_EDGE_THRESHOLD: f32 = 96.0;
+const ROI_PAPER_MIN_LUMA: u8 = 128;
+const ROI_PAPER_MAX_CHROMA: u8 = 48;
+
+// Auto exposure: mean luminance to aim for, how far off it may be before
+// correcting, and the strongest gamma applied either way
+const AUTO_EXPOSURE_TARGET: f32 = 0.45;
+const AUTO_EXPOSURE_TOLERANCE: f32 = 0.03;
+const AUTO_EXPOSURE_MAX_GAMMA: f32 = 2.5;
+
+// Watermark width relative to the frame width, and distance from the edges
+const WATERMARK_SCALE: f32 = 0.2;
+const WATERMARK_MARGIN: u32 = 16;
+
+// Font for burned-in timestamps, bundled so packaged builds don't rely on system fonts
+const TIMESTAMP_FONT: &[u8] = include_bytes!("../fonts/DejaVuSansMono-Bold.ttf");
+// Timestamp text height relative to the frame height when no font size is given
+const TIMESTAMP_SCALE: f32 = 0.04;
+// Thickest timestamp outline in pixels; each pixel of it costs a ring of extra text draws
+const TIMESTAMP_MAX_OUTLINE: i32 = 4;
+
+// Accepted colour temperatures, and the strongest per-channel gain of the software fallback
+const MIN_WHITE_BALANCE_KELVIN: u32 = 1000;
+const MAX_WHITE_BALANCE_KELVIN: u32 = 10000;
+const GRAY_WORLD_MAX_GAIN: f32 = 2.0;
+// V4L2_CID_AUTO_WHITE_BALANCE; nokhwa has no named control for it
+const AUTO_WHITE_BALANCE_CONTROL: u128 = 0x0098_090c;
+
+// V4L2_CID_EXPOSURE_AUTO and V4L2_CID_EXPOSURE_ABSOLUTE (what UVC webcams expose), and
+// the auto-exposure menu value for manual exposure
+const AUTO_EXPOSURE_MODE_CONTROL: u128 = 0x009a_0901;
+const EXPOSURE_ABSOLUTE_CONTROL: u128 = 0x009a_0902;
+const EXPOSURE_MODE_MANUAL: i64 = 1;
+// HDR bracket: exposure factor either side of normal (4x = 2 EV), frames dropped after
+// each change while the sensor settles, and how long capture_hdr waits for the bracket
+const HDR_BRACKET_FACTOR: i64 = 4;
+const HDR_SETTLE_FRAMES: usize = 3;
+const HDR_TIMEOUT: Duration = Duration::from_secs(10);
+// Exposure fusion: spread of the well-exposedness weight around mid-grey, and the blur
+// applied to the weight maps so the blend has no visible seams
+const FUSION_SIGMA: f32 = 0.2;
+const FUSION_WEIGHT_BLUR: f32 = 8.0;
+
+// Most frames measure_capture_latency may time, and how long it waits per frame
+const MAX_LATENCY_SAMPLES: u32 = 300;
+const LATENCY_TIMEOUT_PER_SAMPLE: Duration = Duration::from_secs(1);
+
 /// Camera frame event payload
 #[derive(Clone, serde::Serialize)]
 pub struct CameraFrame {
-    pub data: String, // base64 encoded JPEG
+    pub data: String, // JPEG data URL, or plain base64 YUYV 4:2:2 bytes
+    pub width: u32,
+    pub height: u32,
+    pub format: String, // "jpeg" or "yuyv"
+    pub stream: String, // "color", or "infrared" for single-channel (grayscale) frames
+}
+
+/// Resolution, frame rate and pixel format the camera is streaming at
+#[derive(Clone, serde::Serialize)]
+pub struct CameraFormatInfo {
     pub width: u32,
     pub height: u32,
+    pub frame_rate: u32,
+    pub pixel_format: String, // e.g. "MJPEG", "YUYV"
+    pub backend: String,      // e.g. "Video4Linux"
+}
+
+/// Preview stream statistics over the last few seconds
+#[derive(Clone, serde::Serialize)]
+pub struct PreviewStats {
+    pub fps: f32,            // frames actually emitted per second
+    pub avg_encode_ms: f32,  // decode + downscale + encode time per emitted frame
+    pub dropped_frames: u32, // frames that failed to capture, decode or encode
+    pub window_ms: u64,      // span the numbers were measured over
+}
+
+/// Preview stream settings, adjustable while the stream runs
+#[derive(Clone, Copy, serde::Serialize, serde::Deserialize)]
+pub struct PreviewConfig {
+    pub fps: u32,
+    pub scale: f32, // preview size relative to the captured frame (0.1-1.0)
+    pub jpeg_quality: u8,
+}
+
+impl Default for PreviewConfig {
+    fn default() -> Self {
+        PreviewConfig {
+            fps: TARGET_FPS as u32,
+            scale: 1.0,
+            jpeg_quality: JPEG_QUALITY,
+        }
+    }
+}
+
+impl PreviewConfig {
+    /// Check every setting is in range, describing the first one that isn't
+    fn validate(&self) -> Result<(), String> {
+        if self.fps == 0 || self.fps > MAX_PREVIEW_FPS {
+            return Err(format!(
+                "Preview FPS must be between 1 and {}",
+                MAX_PREVIEW_FPS
+            ));
+        }
+        if !(MIN_PREVIEW_SCALE..=1.0).contains(&self.scale) {
+            return Err(format!(
+                "Preview scale must be between {} and 1.0",
+                MIN_PREVIEW_SCALE
+            ));
+        }
+        if !(1..=100).contains(&self.jpeg_quality) {
+            return Err("JPEG quality must be between 1 and 100".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Time the camera took to deliver each frame, in milliseconds
+#[derive(Clone, serde::Serialize)]
+pub struct LatencyStats {
+    pub samples: u32,
+    pub min_ms: f32,
+    pub avg_ms: f32,
+    pub max_ms: f32,
+}
+
+/// Range and value of one camera control, for building a slider
+/// `min`/`max` are None for controls without bounds; switches report 0/1.
+#[derive(Clone, serde::Serialize)]
+pub struct CameraControlRange {
+    pub name: String, // e.g. "brightness", "white_balance"
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    pub step: f64,
+    pub default: f64,
+    pub value: f64,
+}
+
+/// Whether a camera is free to open
+#[derive(Clone, serde::Serialize)]
+pub struct CameraStatus {
+    pub index: u32,
+    pub name: String,
+    pub in_use_by_app: bool,            // held open by this app's stream
+    pub in_use_elsewhere: Option<bool>, // open in another process; None when it can't be checked
+}
+
+/// Resolution fallback event payload
+#[derive(Clone, serde::Serialize)]
+pub struct CameraFallback {
+    pub from_width: u32,
+    pub from_height: u32,
+    pub to_width: u32,
+    pub to_height: u32,
+    pub error: String,
+}
+
+/// Camera open retry event payload
+#[derive(Clone, serde::Serialize)]
+pub struct CameraInitRetry {
+    pub attempt: u32,
+    pub max_attempts: u32,
+    pub error: String,
 }
 
 /// Camera error event payload
@@ -55,33 +313,210 @@ pub struct CameraError {
 #[derive(Clone, serde::Serialize)]
 pub struct PhotoSaved {
     pub path: String,
+    pub width: u32, // saved image size, 0 on failure
+    pub height: u32,
     pub success: bool,
     pub error: Option<String>,
 }
 
-/// Start camera streaming
+/// Measurements of a frame region, for document alignment hints
+#[derive(Clone, serde::Serialize)]
+pub struct RoiStats {
+    pub mean_brightness: f32, // 0.0-1.0
+    pub edge_density: f32,    // share of pixels on a Sobel edge, 0.0-1.0
+    pub fill_ratio: f32,      // share of bright, near-neutral (paper-like) pixels, 0.0-1.0
+}
+
+/// Self-timer countdown event payload
+#[derive(Clone, serde::Serialize)]
+pub struct CaptureCountdown {
+    pub seconds_left: u64,
+    pub remaining_ms: u64,
+}
+
+/// Self-timer result: the captured frame, or the saved image when `save` was set
+#[derive(Clone, serde::Serialize)]
+pub struct CaptureComplete {
+    pub frame: Option<CameraFrame>,
+    pub image: Option<GalleryImage>,
+}
+
+/// Timelapse frame saved event payload
+#[derive(Clone, serde::Serialize)]
+pub struct TimelapseFrame {
+    pub count: u64,
+    pub path: String,
+}
+
+/// PNG sequence frame saved event payload
+#[derive(Clone, serde::Serialize)]
+pub struct PngSequenceProgress {
+    pub count: u64,
+    pub path: String,
+}
+
+/// Pixel format delivered in `camera-frame` events
+#[derive(Clone, Copy, Default, PartialEq, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum StreamPixelFormat {
+    /// Decode to RGB and send JPEG
+    #[default]
+    Rgb,
+    /// Send the camera's YUYV bytes untouched; the frontend converts them
+    Yuyv,
+    /// Stream the camera's 8-bit grayscale (infrared) format and send grayscale JPEG
+    Infrared,
+}
+
+/// Per-stream settings handed to the camera thread
+struct StreamOptions {
+    backend: ApiBackend,
+    pixel_format: StreamPixelFormat,
+    resolution: Resolution,
+    preview_width: Option<u32>,
+    open_attempts: u32,
+    retry_delay: Duration,
+}
+
+/// Per-frame outcomes within the stats window; `None` marks a dropped frame
+#[derive(Default)]
+struct PreviewCounters {
+    started: Option<Instant>,
+    frames: VecDeque<(Instant, Option<Duration>)>,
+}
+
+/// Raw YUYV 4:2:2 frame as delivered by the camera
+struct YuyvFrame {
+    width: u32,
+    height: u32,
+    data: Vec<u8>,
+}
+
+/// Corner where the watermark is placed on saved photos
+#[derive(Clone, Copy, Default, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WatermarkPosition {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    #[default]
+    BottomRight,
+}
+
+/// Burned-in timestamp options for saved photos
+#[derive(Clone, Copy, Default, serde::Deserialize)]
+pub struct TimestampOverlay {
+    pub font_size: Option<f32>, // pixels, defaults to a fraction of the frame height
+    pub position: Option<WatermarkPosition>, // defaults to bottom-left
+}
+
+/// Named white balance presets
+#[derive(Clone, Copy, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WhiteBalancePreset {
+    Auto,
+    Daylight,
+    Fluorescent,
+    Incandescent,
+}
+
+/// White balance as a preset name or a colour temperature in kelvin
+#[derive(Clone, Copy, serde::Deserialize)]
+#[serde(untagged)]
+pub enum WhiteBalanceSetting {
+    Kelvin(u32),
+    Preset(WhiteBalancePreset),
+}
+
+impl WhiteBalanceSetting {
+    /// Colour temperature to set, or None for automatic white balance
+    fn kelvin(self) -> Option<u32> {
+        match self {
+            WhiteBalanceSetting::Kelvin(kelvin) => Some(kelvin),
+            WhiteBalanceSetting::Preset(WhiteBalancePreset::Auto) => None,
+            WhiteBalanceSetting::Preset(WhiteBalancePreset::Daylight) => Some(5500),
+            WhiteBalanceSetting::Preset(WhiteBalancePreset::Fluorescent) => Some(4000),
+            WhiteBalanceSetting::Preset(WhiteBalancePreset::Incandescent) => Some(2800),
+        }
+    }
+}
+
+/// How a white balance setting was applied
+#[derive(Clone, serde::Serialize)]
+pub struct WhiteBalanceApplied {
+    pub method: String,      // "hardware" or "software"
+    pub kelvin: Option<i64>, // temperature the camera accepted; None for auto or software
+}
+
+// Setting to apply, and where to send the outcome
+type WhiteBalanceRequest = (WhiteBalanceSetting, Sender<WhiteBalanceApplied>);
+
+// Receives the under-, normal- and over-exposed frames, or why they couldn't be taken
+type HdrRequest = Sender<Result<Vec<RgbImage>, String>>;
+
+// Number of frames to time, and where to send each frame's capture time
+type LatencyRequest = (u32, Sender<Result<Vec<Duration>, String>>);
+
+/// Start camera streaming (RGB/JPEG frames unless `pixel_format` is "yuyv" or "infrared")
+/// "infrared" picks the camera's 8-bit grayscale stream, as IR cameras expose it, and
+/// sends single-channel frames tagged `stream: "infrared"`; a color-only camera fails with
+/// `camera-error`. Depth streams (16-bit) can't be captured.
+/// `preview_width` downscales the emitted preview only; captures stay full resolution.
+/// `open_attempts`/`retry_delay_ms` retry opening the camera (e.g. not yet enumerated on boot).
+/// `backend` forces a capture API ("v4l2", "avfoundation", "mediafoundation"); unknown or
+/// unavailable backends fall back to "auto".
+/// `resolution` is tried first (default 640x480); if it won't stream, smaller standard
+/// modes (1080p, 720p, 480p, 240p, 120p) are tried in turn, each emitting `camera-fallback`.
 #[tauri::command]
-pub async fn start_camera_stream(app: AppHandle) -> Result<String, String> {
+pub async fn start_camera_stream(
+    app: AppHandle,
+    backend: Option<String>,
+    pixel_format: Option<StreamPixelFormat>,
+    resolution: Option<(u32, u32)>,
+    preview_width: Option<u32>,
+    open_attempts: Option<u32>,
+    retry_delay_ms: Option<u64>,
+) -> Result<String, AppError> {
     // Check if already running
     if CAMERA_RUNNING.load(Ordering::SeqCst) {
         return Ok("Camera already running".to_string());
     }
 
-    // Reset stop signal
+    let (width, height) = resolution.unwrap_or((CAMERA_WIDTH, CAMERA_HEIGHT));
+    if width == 0 || height == 0 {
+        return Err(AppError::InvalidArgument(
+            "Resolution must be non-zero".to_string(),
+        ));
+    }
+
+    // Reset stop and pause signals
     STOP_SIGNAL.store(false, Ordering::SeqCst);
+    PAUSE_REQUESTED.store(false, Ordering::SeqCst);
 
     // Spawn camera thread
+    let options = StreamOptions {
+        backend: parse_camera_backend(backend.as_deref()),
+        pixel_format: pixel_format.unwrap_or_default(),
+        resolution: Resolution::new(width, height),
+        preview_width: preview_width.filter(|&w| w > 0),
+        open_attempts: open_attempts.unwrap_or(1).max(1),
+        retry_delay: Duration::from_millis(retry_delay_ms.unwrap_or(DEFAULT_OPEN_RETRY_DELAY_MS)),
+    };
     let app_handle = app.clone();
     thread::spawn(move || {
-        run_camera_stream(app_handle);
+        run_camera_stream(app_handle, options);
     });
 
     Ok("Camera stream started".to_string())
 }
 
 /// Stop camera streaming
+/// Background tasks that read the stream are stopped first, so none of them keeps
+/// polling a camera that's gone.
 #[tauri::command]
-pub async fn stop_camera_stream() -> Result<String, String> {
+pub async fn stop_camera_stream() -> Result<String, AppError> {
+    stop_camera_tasks().await?;
+
     if !CAMERA_RUNNING.load(Ordering::SeqCst) {
         return Ok("Camera not running".to_string());
     }
@@ -97,186 +532,2413 @@ pub async fn stop_camera_stream() -> Result<String, String> {
     }
 
     if CAMERA_RUNNING.load(Ordering::SeqCst) {
-        return Err("Camera failed to stop in time".to_string());
+        return Err("Camera failed to stop in time".into());
     }
 
     Ok("Camera stream stopped".to_string())
 }
 
-/// Capture and save a photo from the current stream
-#[tauri::command]
-pub async fn capture_photo(app: AppHandle) -> Result<PhotoSaved, String> {
-    // Get the latest JPEG frame from the shared buffer
-    let jpeg_data = {
-        let guard = LATEST_FRAME.read();
-        guard.clone()
+/// Stop the timelapse, PNG sequence, ring buffer, self-timer and sound trigger, waiting for
+/// each to exit
+async fn stop_camera_tasks() -> Result<(), String> {
+    let running = || {
+        TIMELAPSE_RUNNING.load(Ordering::SeqCst)
+            || PNG_SEQUENCE_RUNNING.load(Ordering::SeqCst)
+            || RINGBUFFER_RUNNING.load(Ordering::SeqCst)
+            || CAPTURE_TIMER_RUNNING.load(Ordering::SeqCst)
     };
 
-    let data = match jpeg_data {
-        Some(d) => d,
-        None => {
-            let result = PhotoSaved {
-                path: String::new(),
-                success: false,
-                error: Some("No frame available. Is the camera streaming?".to_string()),
-            };
-            let _ = app.emit("photo-saved", result.clone());
-            return Ok(result);
-        }
-    };
+    STOP_CAMERA_TASKS.store(true, Ordering::SeqCst);
+
+    // Wait for the task threads to finish (with timeout)
+    let mut attempts = 0;
+    while running() && attempts < 50 {
+        thread::sleep(Duration::from_millis(50));
+        attempts += 1;
+    }
+    let stopped = !running();
+    STOP_CAMERA_TASKS.store(false, Ordering::SeqCst);
 
-    // Get Pictures directory
-    let pictures_dir = dirs::picture_dir().ok_or("Failed to get Pictures directory")?;
-    let camera_dir = pictures_dir.join("honeybee-camera");
+    // The sound trigger listens on the microphone and keeps its own thread
+    stop_sound_triggered_capture().await?;
+
+    if !stopped {
+        return Err("Camera tasks failed to stop in time".to_string());
+    }
+    Ok(())
+}
 
-    // Create directory if needed
-    if !camera_dir.exists() {
-        std::fs::create_dir_all(&camera_dir)
-            .map_err(|e| format!("Failed to create camera directory: {}", e))?;
+/// Check that the camera at `index` opens and streams, without touching the live stream
+/// Returns the format it negotiated; `grab_frame` also waits for one frame to arrive.
+#[tauri::command]
+pub async fn test_camera(
+    index: u32,
+    grab_frame: Option<bool>,
+    backend: Option<String>,
+) -> Result<CameraFormatInfo, AppError> {
+    // The stream holds camera 0 open, and the device can't be opened twice
+    if index == 0 && CAMERA_RUNNING.load(Ordering::SeqCst) {
+        return Err("Camera 0 is in use by the running stream".into());
     }
 
-    // Generate filename
-    let timestamp = Local::now().format("%Y%m%d_%H%M%S").to_string();
-    let filename = format!("IMG_{}.jpg", timestamp);
-    let filepath = camera_dir.join(&filename);
+    let backend = parse_camera_backend(backend.as_deref());
+    let info = tauri::async_runtime::spawn_blocking(move || {
+        let requested = requested_format(StreamPixelFormat::Rgb, default_resolution());
+        let mut camera = open_camera(index, requested, backend)?;
+        let info = format_info(&camera);
 
-    // Write JPEG directly to file
-    if let Err(e) = std::fs::write(&filepath, data) {
-        let result = PhotoSaved {
-            path: String::new(),
-            success: false,
-            error: Some(format!("Failed to save photo: {}", e)),
+        let frame = if grab_frame.unwrap_or(false) {
+            camera
+                .frame()
+                .map(|_| ())
+                .map_err(|e| format!("Camera opened but delivered no frame: {}", e))
+        } else {
+            Ok(())
         };
-        let _ = app.emit("photo-saved", result.clone());
-        return Ok(result);
+
+        // Close before reporting so the device is free again either way
+        let _ = camera.stop_stream();
+        frame.map(|_| info)
+    })
+    .await
+    .map_err(|e| format!("Failed to test camera: {}", e))??;
+    Ok(info)
+}
+
+/// Report whether the camera at `index` is already open, here or in another process
+/// The other-process check scans /proc on Linux, so it only sees processes we may inspect.
+#[tauri::command]
+pub async fn camera_status(index: u32) -> Result<CameraStatus, AppError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let cameras = nokhwa::query(ApiBackend::Auto)
+            .map_err(|e| format!("Failed to list cameras: {}", e))?;
+        let camera = cameras
+            .iter()
+            .find(|c| c.index().as_index().ok() == Some(index))
+            .ok_or_else(|| format!("Camera {} not found", index))?;
+
+        Ok(CameraStatus {
+            index,
+            name: camera.human_name(),
+            // The stream always runs on camera 0
+            in_use_by_app: index == 0 && CAMERA_RUNNING.load(Ordering::SeqCst),
+            in_use_elsewhere: device_open_elsewhere(index),
+        })
+    })
+    .await
+    .map_err(|e| format!("Failed to check camera status: {}", e))?
+}
+
+/// Pause the camera stream without releasing the device
+/// Frames stop (and captures fail with "Camera paused") until `resume_camera`,
+/// which is much quicker than reopening the camera.
+#[tauri::command]
+pub async fn pause_camera() -> Result<String, AppError> {
+    if !CAMERA_RUNNING.load(Ordering::SeqCst) {
+        return Err(AppError::CameraNotInitialized(
+            "Camera not running".to_string(),
+        ));
+    }
+    if CAMERA_PAUSED.load(Ordering::SeqCst) {
+        return Ok("Camera already paused".to_string());
     }
 
-    let path_str = filepath.to_string_lossy().to_string();
-    let result = PhotoSaved {
-        path: path_str.clone(),
-        success: true,
-        error: None,
-    };
+    PAUSE_REQUESTED.store(true, Ordering::SeqCst);
 
-    let _ = app.emit("photo-saved", result.clone());
-    Ok(result)
+    // Wait for the stream thread to pause (with timeout)
+    let mut attempts = 0;
+    while !CAMERA_PAUSED.load(Ordering::SeqCst)
+        && CAMERA_RUNNING.load(Ordering::SeqCst)
+        && attempts < 50
+    {
+        thread::sleep(Duration::from_millis(50));
+        attempts += 1;
+    }
+
+    if !CAMERA_PAUSED.load(Ordering::SeqCst) {
+        PAUSE_REQUESTED.store(false, Ordering::SeqCst);
+        return Err("Camera failed to pause in time".into());
+    }
+
+    Ok("Camera paused".to_string())
 }
 
-/// Internal function to run camera stream
-fn run_camera_stream(app: AppHandle) {
-    CAMERA_RUNNING.store(true, Ordering::SeqCst);
+/// Resume a stream paused with `pause_camera`
+#[tauri::command]
+pub async fn resume_camera() -> Result<String, AppError> {
+    if !CAMERA_RUNNING.load(Ordering::SeqCst) {
+        return Err(AppError::CameraNotInitialized(
+            "Camera not running".to_string(),
+        ));
+    }
+    if !CAMERA_PAUSED.load(Ordering::SeqCst) {
+        return Ok("Camera not paused".to_string());
+    }
 
-    // Create camera at 640x480
-    let requested = RequestedFormat::new::<RgbFormat>(RequestedFormatType::Closest(
-        CameraFormat::new(
-            Resolution::new(CAMERA_WIDTH, CAMERA_HEIGHT),
-            FrameFormat::MJPEG,
-            TARGET_FPS as u32,
-        ),
-    ));
+    PAUSE_REQUESTED.store(false, Ordering::SeqCst);
 
-    let mut camera = match Camera::new(CameraIndex::Index(0), requested) {
-        Ok(cam) => cam,
-        Err(e) => {
-            let _ = app.emit(
-                "camera-error",
-                CameraError {
-                    message: format!("Failed to open camera: {}", e),
-                },
-            );
-            CAMERA_RUNNING.store(false, Ordering::SeqCst);
-            return;
-        }
-    };
+    // Wait for the stream thread to reopen the stream (with timeout)
+    let mut attempts = 0;
+    while CAMERA_PAUSED.load(Ordering::SeqCst)
+        && CAMERA_RUNNING.load(Ordering::SeqCst)
+        && attempts < 50
+    {
+        thread::sleep(Duration::from_millis(50));
+        attempts += 1;
+    }
 
-    // Open stream
-    if let Err(e) = camera.open_stream() {
-        let _ = app.emit(
-            "camera-error",
-            CameraError {
-                message: format!("Failed to start camera stream: {}", e),
-            },
-        );
-        CAMERA_RUNNING.store(false, Ordering::SeqCst);
-        return;
+    // A failed reopen ends the stream and emits `camera-error`
+    if !CAMERA_RUNNING.load(Ordering::SeqCst) {
+        return Err("Camera stopped while resuming".into());
+    }
+    if CAMERA_PAUSED.load(Ordering::SeqCst) {
+        return Err("Camera failed to resume in time".into());
     }
 
-    let frame_interval = Duration::from_millis(1000 / TARGET_FPS);
+    Ok("Camera resumed".to_string())
+}
 
-    // Main capture loop
-    loop {
-        // Check stop signal
-        if STOP_SIGNAL.load(Ordering::SeqCst) {
-            break;
+/// Set white balance to a preset ("auto", "daylight", "fluorescent", "incandescent") or a
+/// temperature in kelvin (1000-10000)
+/// Uses the camera's own control where it has one; otherwise saved photos get a software
+/// gray-world correction instead. Lasts until the stream stops.
+#[tauri::command]
+pub async fn set_white_balance(
+    setting: WhiteBalanceSetting,
+) -> Result<WhiteBalanceApplied, AppError> {
+    if setting
+        .kelvin()
+        .is_some_and(|k| !(MIN_WHITE_BALANCE_KELVIN..=MAX_WHITE_BALANCE_KELVIN).contains(&k))
+    {
+        return Err(AppError::InvalidArgument(format!(
+            "White balance must be between {} and {} K",
+            MIN_WHITE_BALANCE_KELVIN, MAX_WHITE_BALANCE_KELVIN
+        )));
+    }
+    if !CAMERA_RUNNING.load(Ordering::SeqCst) {
+        return Err(AppError::CameraNotInitialized(
+            "Camera not running".to_string(),
+        ));
+    }
+
+    // The stream thread owns the camera; hand the change over and wait for its answer
+    let (reply, applied) = channel();
+    *WHITE_BALANCE_REQUEST.lock() = Some((setting, reply));
+
+    match wait_for_stream(applied, Duration::from_millis(2500)).await {
+        Ok(result) => Ok(result),
+        Err(RecvTimeoutError::Disconnected) => {
+            Err(AppError::CameraNotInitialized("Camera stopped".to_string()))
         }
+        Err(RecvTimeoutError::Timeout) => {
+            WHITE_BALANCE_REQUEST.lock().take();
+            Err("Camera failed to apply white balance in time".into())
+        }
+    }
+}
 
-        let frame_start = std::time::Instant::now();
+/// Wait for the stream thread's answer on a blocking thread, keeping the async runtime free
+async fn wait_for_stream<T: Send + 'static>(
+    reply: Receiver<T>,
+    timeout: Duration,
+) -> Result<T, RecvTimeoutError> {
+    tauri::async_runtime::spawn_blocking(move || reply.recv_timeout(timeout))
+        .await
+        .unwrap_or(Err(RecvTimeoutError::Disconnected))
+}
 
-        // Capture frame
-        match camera.frame() {
-            Ok(frame) => {
-                // Decode to RGB
-                if let Ok(decoded) = frame.decode_image::<RgbFormat>() {
-                    // Create image buffer
-                    if let Some(img) = ImageBuffer::<Rgb<u8>, Vec<u8>>::from_raw(
-                        decoded.width(),
-                        decoded.height(),
-                        decoded.into_raw(),
-                    ) {
-                        // Encode to JPEG once - used for both streaming and capture
-                        let mut jpeg_buffer = Cursor::new(Vec::new());
-                        if image::codecs::jpeg::JpegEncoder::new_with_quality(
-                            &mut jpeg_buffer,
-                            JPEG_QUALITY,
-                        )
-                        .encode_image(&img)
-                        .is_ok()
-                        {
-                            let jpeg_bytes = jpeg_buffer.into_inner();
+/// List the standard controls the camera supports, with their range and current value
+/// Controls that aren't numeric or on/off are left out; a camera without any gives an
+/// empty list. Works while the stream is paused.
+#[tauri::command]
+pub async fn get_camera_control_ranges() -> Result<Vec<CameraControlRange>, AppError> {
+    if !CAMERA_RUNNING.load(Ordering::SeqCst) {
+        return Err(AppError::CameraNotInitialized(
+            "Camera not running".to_string(),
+        ));
+    }
 
-                            // Store JPEG for capture
-                            {
-                                let mut guard = LATEST_FRAME.write();
-                                *guard = Some(jpeg_bytes.clone());
-                            }
+    // The stream thread owns the camera; hand the query over and wait for its answer
+    let (reply, ranges) = channel();
+    *CONTROL_RANGES_REQUEST.lock() = Some(reply);
 
-                            // Convert to base64 and emit
-                            let base64_data = STANDARD.encode(&jpeg_bytes);
-                            let _ = app.emit(
-                                "camera-frame",
-                                CameraFrame {
-                                    data: format!("data:image/jpeg;base64,{}", base64_data),
-                                    width: img.width(),
-                                    height: img.height(),
-                                },
-                            );
-                        }
-                    }
-                }
-            }
-            Err(e) => {
-                eprintln!("Camera frame error: {}", e);
-            }
+    match wait_for_stream(ranges, Duration::from_millis(2500)).await {
+        Ok(ranges) => Ok(ranges),
+        Err(RecvTimeoutError::Disconnected) => {
+            Err(AppError::CameraNotInitialized("Camera stopped".to_string()))
         }
-
-        // Maintain target FPS
-        let elapsed = frame_start.elapsed();
-        if elapsed < frame_interval {
-            thread::sleep(frame_interval - elapsed);
+        Err(RecvTimeoutError::Timeout) => {
+            CONTROL_RANGES_REQUEST.lock().take();
+            Err("Camera failed to report its controls in time".into())
         }
     }
+}
 
-    // Cleanup
-    let _ = camera.stop_stream();
-    
-    // Clear the frame buffer
-    {
-        let mut guard = LATEST_FRAME.write();
-        *guard = None;
+/// Capture under-, normal- and over-exposed frames (-2/0/+2 EV) and fuse them into one
+/// better-exposed image
+/// The preview stalls for the second or so it takes to bracket the exposure. Cameras
+/// without a manual exposure control get the current frame instead, with `hdr-fallback`
+/// emitted to say why.
+#[tauri::command]
+pub async fn capture_hdr(app: AppHandle) -> Result<CameraFrame, AppError> {
+    if !CAMERA_RUNNING.load(Ordering::SeqCst) || CAMERA_PAUSED.load(Ordering::SeqCst) {
+        return Err(AppError::CameraNotInitialized(no_frame_error()));
     }
-    
-    CAMERA_RUNNING.store(false, Ordering::SeqCst);
-    STOP_SIGNAL.store(false, Ordering::SeqCst);
+
+    // The stream thread owns the camera; hand the bracket over and wait for the frames
+    let (reply, bracketed) = channel();
+    *HDR_REQUEST.lock() = Some(reply);
+    let bracket = match wait_for_stream(bracketed, HDR_TIMEOUT).await {
+        Ok(result) => result,
+        Err(RecvTimeoutError::Disconnected) => {
+            return Err(AppError::CameraNotInitialized("Camera stopped".to_string()))
+        }
+        Err(RecvTimeoutError::Timeout) => {
+            HDR_REQUEST.lock().take();
+            return Err("Camera failed to capture the exposure bracket in time".into());
+        }
+    };
+
+    let merged = match bracket {
+        Ok(frames) => tauri::async_runtime::spawn_blocking(move || fuse_exposures(&frames))
+            .await
+            .map_err(|e| format!("Failed to merge exposures: {}", e))?,
+        Err(e) => {
+            let message = format!("Exposure bracketing unavailable, using one frame: {}", e);
+            eprintln!("{}", message);
+            let _ = app.emit("hdr-fallback", CameraError { message });
+            latest_frame_rgb()?
+        }
+    };
+
+    let jpeg = encode_jpeg(&merged)?;
+    Ok(store_last_capture(&jpeg, merged.width(), merged.height()))
+}
+
+/// Time `samples` consecutive frame captures and report min/avg/max per frame
+/// This is how long each capture blocks: the camera's real frame interval plus
+/// transfer time, before any decoding. The preview stalls while the frames are timed.
+#[tauri::command]
+pub async fn measure_capture_latency(samples: u32) -> Result<LatencyStats, AppError> {
+    if samples == 0 || samples > MAX_LATENCY_SAMPLES {
+        return Err(AppError::InvalidArgument(format!(
+            "Samples must be between 1 and {}",
+            MAX_LATENCY_SAMPLES
+        )));
+    }
+    if !CAMERA_RUNNING.load(Ordering::SeqCst) || CAMERA_PAUSED.load(Ordering::SeqCst) {
+        return Err(AppError::CameraNotInitialized(no_frame_error()));
+    }
+
+    // The stream thread owns the camera; it times the frames between two preview frames
+    let (reply, timed) = channel();
+    *LATENCY_REQUEST.lock() = Some((samples, reply));
+    let timeout = LATENCY_TIMEOUT_PER_SAMPLE * samples;
+    let timings = match wait_for_stream(timed, timeout).await {
+        Ok(result) => result?,
+        Err(RecvTimeoutError::Disconnected) => {
+            return Err(AppError::CameraNotInitialized("Camera stopped".to_string()))
+        }
+        Err(RecvTimeoutError::Timeout) => {
+            LATENCY_REQUEST.lock().take();
+            return Err("Camera failed to deliver the frames in time".into());
+        }
+    };
+
+    let ms: Vec<f32> = timings.iter().map(|t| t.as_secs_f32() * 1000.0).collect();
+    Ok(LatencyStats {
+        samples,
+        min_ms: ms.iter().copied().fold(f32::INFINITY, f32::min),
+        avg_ms: ms.iter().sum::<f32>() / ms.len() as f32,
+        max_ms: ms.iter().copied().fold(0.0, f32::max),
+    })
+}
+
+/// Check if the camera stream is running (camera opened and delivering frames)
+#[tauri::command]
+pub async fn is_camera_running() -> Result<bool, AppError> {
+    Ok(CAMERA_RUNNING.load(Ordering::SeqCst))
+}
+
+/// Get the format negotiated with the camera, available as soon as the stream opens
+#[tauri::command]
+pub async fn get_camera_format() -> Result<CameraFormatInfo, AppError> {
+    let guard = CAMERA_FORMAT.read();
+    guard
+        .clone()
+        .ok_or_else(|| AppError::CameraNotInitialized("Camera not running".to_string()))
+}
+
+/// Get frame rate, encode time and dropped frames of the preview stream
+#[tauri::command]
+pub async fn preview_stats() -> Result<PreviewStats, AppError> {
+    if !CAMERA_RUNNING.load(Ordering::SeqCst) {
+        return Err(AppError::CameraNotInitialized(
+            "Camera not running".to_string(),
+        ));
+    }
+
+    let counters = PREVIEW_COUNTERS.read();
+    let window = counters
+        .started
+        .map_or(Duration::ZERO, |at| at.elapsed().min(PREVIEW_STATS_WINDOW));
+    let cutoff = Instant::now() - window;
+    let recent = counters.frames.iter().filter(|(at, _)| *at >= cutoff);
+
+    let mut delivered = 0u32;
+    let mut dropped_frames = 0u32;
+    let mut encode_total = Duration::ZERO;
+    for (_, encode_time) in recent {
+        match encode_time {
+            Some(t) => {
+                delivered += 1;
+                encode_total += *t;
+            }
+            None => dropped_frames += 1,
+        }
+    }
+
+    let fps = match window.as_secs_f32() {
+        secs if secs > 0.0 => delivered as f32 / secs,
+        _ => 0.0,
+    };
+    let avg_encode_ms = match delivered {
+        0 => 0.0,
+        n => encode_total.as_secs_f32() * 1000.0 / n as f32,
+    };
+
+    Ok(PreviewStats {
+        fps,
+        avg_encode_ms,
+        dropped_frames,
+        window_ms: window.as_millis() as u64,
+    })
+}
+
+/// Set and persist the preview frame rate, scale and JPEG quality
+/// A running stream picks the new values up on its next frame. `scale` only applies when
+/// the stream was started without `preview_width`; captures always stay full resolution
+/// and full quality. YUYV passthrough frames are only affected by `fps`.
+#[tauri::command]
+pub async fn set_preview_config(fps: u32, scale: f32, jpeg_quality: u8) -> Result<(), AppError> {
+    let config = PreviewConfig {
+        fps,
+        scale,
+        jpeg_quality,
+    };
+    config.validate().map_err(AppError::InvalidArgument)?;
+    let path = preview_config_path().ok_or("Failed to get home directory")?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| AppError::IoError(format!("Failed to create config directory: {}", e)))?;
+    }
+    let data = serde_json::to_vec_pretty(&config)
+        .map_err(|e| format!("Failed to encode preview config: {}", e))?;
+    fs::write(&path, data)
+        .map_err(|e| AppError::IoError(format!("Failed to save preview config: {}", e)))?;
+
+    *PREVIEW_CONFIG.write() = config;
+    Ok(())
+}
+
+/// Get the preview frame rate, scale and JPEG quality
+#[tauri::command]
+pub async fn get_preview_config() -> Result<PreviewConfig, AppError> {
+    Ok(*PREVIEW_CONFIG.read())
+}
+
+/// Path of the persisted preview config (~/.config/honeybee/preview.json)
+fn preview_config_path() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".config/honeybee/preview.json"))
+}
+
+/// Load the saved preview config; a missing, unreadable or out-of-range file means the
+/// defaults (an FPS of 0 would stop every stream)
+fn load_preview_config() -> PreviewConfig {
+    preview_config_path()
+        .and_then(|path| fs::read(path).ok())
+        .and_then(|data| serde_json::from_slice::<PreviewConfig>(&data).ok())
+        .filter(|config| config.validate().is_ok())
+        .unwrap_or_default()
+}
+
+/// Capture and save a photo from the current stream
+/// The capture time is always written to EXIF; `description`/`artist` are added when given.
+/// `aspect_ratio` (e.g. [3, 4]) center-crops the frame to that shape before saving.
+/// `auto_exposure` brightens or darkens the photo in software toward a fixed mean luminance.
+/// `timestamp` burns the capture date/time into a corner of the photo; off when omitted.
+/// Its font size is capped at the frame height.
+/// Photos are white balanced in software when `set_white_balance` fell back to it.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn capture_photo(
+    app: AppHandle,
+    watermark_path: Option<String>,
+    watermark_position: Option<WatermarkPosition>,
+    description: Option<String>,
+    artist: Option<String>,
+    aspect_ratio: Option<(u32, u32)>,
+    auto_exposure: Option<bool>,
+    timestamp: Option<TimestampOverlay>,
+) -> Result<PhotoSaved, AppError> {
+    if aspect_ratio.is_some_and(|(w, h)| w == 0 || h == 0) {
+        return Err(AppError::InvalidArgument(
+            "Aspect ratio parts must be greater than zero".to_string(),
+        ));
+    }
+    let font_size = timestamp.and_then(|t| t.font_size);
+    if font_size.is_some_and(|size| !size.is_finite() || size <= 0.0) {
+        return Err(AppError::InvalidArgument(
+            "Timestamp font size must be greater than zero".to_string(),
+        ));
+    }
+
+    // Get the latest JPEG frame from the shared buffer
+    let data = match latest_frame_jpeg() {
+        Some(d) => d,
+        None => return Ok(photo_failed(&app, no_frame_error())),
+    };
+
+    // Only use the watermark if it exists
+    let watermark_path = watermark_path.filter(|wm_path| {
+        let exists = Path::new(wm_path).exists();
+        if !exists {
+            eprintln!("Watermark not found, saving without it: {}", wm_path);
+        }
+        exists
+    });
+
+    // Crop, exposure, watermark and timestamp work on the decoded pixels;
+    // otherwise save the streamed JPEG as-is
+    let now = Local::now();
+    let auto_exposure = auto_exposure.unwrap_or(false);
+    let white_balance = SOFTWARE_WHITE_BALANCE.load(Ordering::SeqCst);
+    let edit_pixels = aspect_ratio.is_some()
+        || auto_exposure
+        || white_balance
+        || watermark_path.is_some()
+        || timestamp.is_some();
+    let (data, width, height) = if edit_pixels {
+        let mut img = latest_frame_rgb()?;
+        if let Some((ratio_w, ratio_h)) = aspect_ratio {
+            img = crop_to_aspect(&img, ratio_w, ratio_h);
+        }
+        if white_balance {
+            apply_gray_world(&mut img);
+        }
+        // Meter the final framing, before the watermark is drawn on it
+        if auto_exposure {
+            apply_auto_exposure(&mut img);
+        }
+        if let Some(wm_path) = &watermark_path {
+            let position = watermark_position.unwrap_or_default();
+            if let Err(e) = apply_watermark(&mut img, wm_path, position) {
+                return Ok(photo_failed(&app, e));
+            }
+        }
+        if let Some(overlay) = timestamp {
+            let text = now.format("%Y-%m-%d %H:%M:%S").to_string();
+            draw_timestamp(&mut img, &text, overlay)?;
+        }
+        let (width, height) = img.dimensions();
+        (encode_jpeg(&img)?, width, height)
+    } else {
+        let (width, height) = ImageReader::with_format(Cursor::new(&data), ImageFormat::Jpeg)
+            .into_dimensions()
+            .map_err(|e| AppError::DecodeError(format!("Failed to read frame size: {}", e)))?;
+        (data, width, height)
+    };
+
+    // Tag the photo with capture time and station details
+    let data = add_exif_to_jpeg(
+        &data,
+        &PhotoMetadata {
+            captured_at: now,
+            description,
+            artist,
+        },
+    )?;
+
+    // Get (and create if needed) the camera directory
+    let camera_dir = ensure_camera_dir()?;
+
+    // Name by the second, adding _1, _2, ... for further photos in the same second;
+    // create_new keeps a concurrent capture from claiming the same name in between
+    let stem = format!("IMG_{}", now.format("%Y%m%d_%H%M%S"));
+    let (filepath, written) = loop {
+        let filepath = unique_path(&camera_dir, &stem, "jpg");
+        match fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&filepath)
+        {
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => continue,
+            opened => break (filepath, opened.and_then(|mut file| file.write_all(&data))),
+        }
+    };
+    if let Err(e) = written {
+        return Ok(photo_failed(&app, format!("Failed to save photo: {}", e)));
+    }
+
+    store_last_capture(&data, width, height);
+
+    let path_str = filepath.to_string_lossy().to_string();
+    let result = PhotoSaved {
+        path: path_str.clone(),
+        width,
+        height,
+        success: true,
+        error: None,
+    };
+
+    let _ = app.emit("photo-saved", result.clone());
+    enforce_retention(&app).await;
+    Ok(result)
+}
+
+/// Emit `photo-saved` for a capture that failed, so the frontend stops waiting
+fn photo_failed(app: &AppHandle, error: String) -> PhotoSaved {
+    let result = PhotoSaved {
+        path: String::new(),
+        width: 0,
+        height: 0,
+        success: false,
+        error: Some(error),
+    };
+    let _ = app.emit("photo-saved", result.clone());
+    result
+}
+
+/// Get the most recent capture again without taking a new one
+/// Cleared when the camera stream is (re)started.
+#[tauri::command]
+pub async fn get_last_frame() -> Result<CameraFrame, AppError> {
+    LAST_CAPTURE
+        .read()
+        .clone()
+        .ok_or_else(|| "No frame captured yet".into())
+}
+
+/// Capture the current frame as lossless PNG bytes
+/// `max_dimension` downscales the frame so neither side exceeds it (for previews).
+#[tauri::command]
+pub async fn capture_frame_png(max_dimension: Option<u32>) -> Result<Vec<u8>, AppError> {
+    if max_dimension == Some(0) {
+        return Err(AppError::InvalidArgument(
+            "Maximum dimension must be greater than zero".to_string(),
+        ));
+    }
+
+    // Copy the pixels out under the lock, encode after releasing it
+    let frame = latest_frame_rgb()?;
+    let png = match max_dimension {
+        Some(max) if frame.width() > max || frame.height() > max => {
+            encode_png(&fit_within(&frame, max))?
+        }
+        _ => encode_png(&frame)?,
+    };
+    Ok(png)
+}
+
+/// Capture the current full-resolution frame in the background
+/// Emits `frame-captured` with the JPEG frame when done, or `frame-error` on failure.
+#[tauri::command]
+pub async fn capture_frame_async(app: AppHandle) -> Result<String, AppError> {
+    thread::spawn(move || {
+        let captured = latest_frame_rgb().and_then(|frame| {
+            let jpeg = encode_jpeg(&frame)?;
+            Ok(store_last_capture(&jpeg, frame.width(), frame.height()))
+        });
+
+        match captured {
+            Ok(frame) => {
+                let _ = app.emit("frame-captured", frame);
+            }
+            Err(e) => {
+                let message = e.to_string();
+                let _ = app.emit("frame-error", CameraError { message });
+            }
+        }
+    });
+
+    Ok("Frame capture started".to_string())
+}
+
+/// Capture a frame after `delay_ms`, emitting `capture-countdown` each second
+/// Emits `capture-complete` with the frame, or with the saved gallery image when `save`
+/// is set; `capture-error` on failure and `capture-cancelled` if `cancel_capture` is called.
+#[tauri::command]
+pub async fn capture_with_timer(
+    app: AppHandle,
+    delay_ms: u64,
+    save: bool,
+) -> Result<String, AppError> {
+    if !CAMERA_RUNNING.load(Ordering::SeqCst) {
+        return Err(AppError::CameraNotInitialized(
+            "Camera not running".to_string(),
+        ));
+    }
+    if CAPTURE_TIMER_RUNNING.swap(true, Ordering::SeqCst) {
+        return Err("Capture timer already running".into());
+    }
+    CANCEL_CAPTURE.store(false, Ordering::SeqCst);
+
+    thread::spawn(move || {
+        if run_capture_countdown(&app, delay_ms) {
+            match timed_capture(&app, save) {
+                Ok(complete) => {
+                    let _ = app.emit("capture-complete", complete);
+                }
+                Err(message) => {
+                    let _ = app.emit("capture-error", CameraError { message });
+                }
+            }
+        } else {
+            let _ = app.emit("capture-cancelled", ());
+        }
+
+        CAPTURE_TIMER_RUNNING.store(false, Ordering::SeqCst);
+        CANCEL_CAPTURE.store(false, Ordering::SeqCst);
+    });
+
+    Ok("Capture timer started".to_string())
+}
+
+/// Abort a running self-timer before it captures
+#[tauri::command]
+pub async fn cancel_capture() -> Result<String, AppError> {
+    if !CAPTURE_TIMER_RUNNING.load(Ordering::SeqCst) {
+        return Ok("No capture timer running".to_string());
+    }
+
+    CANCEL_CAPTURE.store(true, Ordering::SeqCst);
+
+    // Wait for the timer thread to finish (with timeout)
+    let mut attempts = 0;
+    while CAPTURE_TIMER_RUNNING.load(Ordering::SeqCst) && attempts < 50 {
+        thread::sleep(Duration::from_millis(50));
+        attempts += 1;
+    }
+
+    if CAPTURE_TIMER_RUNNING.load(Ordering::SeqCst) {
+        return Err("Capture timer failed to stop in time".into());
+    }
+
+    Ok("Capture cancelled".to_string())
+}
+
+/// Capture the current frame as RGBA PNG bytes, transparent where it matches `key_color`
+/// A pixel is keyed out when every channel is within `tolerance` of the key colour.
+#[tauri::command]
+pub async fn capture_chroma_key(key_color: [u8; 3], tolerance: u8) -> Result<Vec<u8>, AppError> {
+    let frame = latest_frame_rgb()?;
+
+    let keyed = RgbaImage::from_fn(frame.width(), frame.height(), |x, y| {
+        let Rgb([r, g, b]) = *frame.get_pixel(x, y);
+        let is_key = [r, g, b]
+            .iter()
+            .zip(key_color.iter())
+            .all(|(&c, &k)| c.abs_diff(k) <= tolerance);
+        Rgba([r, g, b, if is_key { 0 } else { 255 }])
+    });
+
+    let mut png_buffer = Cursor::new(Vec::new());
+    keyed
+        .write_to(&mut png_buffer, ImageFormat::Png)
+        .map_err(|e| format!("Failed to encode PNG: {}", e))?;
+    Ok(png_buffer.into_inner())
+}
+
+/// Compare the current frame with the one from the previous call, returning a 0.0-1.0 motion score
+/// Per-pixel differences below `threshold` (0.0-1.0) count as sensor noise and are ignored.
+/// The first call after the stream starts returns 0.0 since there's nothing to compare with.
+#[tauri::command]
+pub async fn detect_motion(threshold: f32) -> Result<f32, AppError> {
+    if !(0.0..=1.0).contains(&threshold) {
+        return Err(AppError::InvalidArgument(
+            "Motion threshold must be between 0.0 and 1.0".to_string(),
+        ));
+    }
+
+    // Compare small grayscale copies; that's plenty for motion and much cheaper
+    let frame = latest_frame_rgb()?;
+    let small = scale_to_width(&frame, MOTION_SAMPLE_WIDTH.min(frame.width()));
+    let current = DynamicImage::ImageRgb8(small).to_luma8();
+
+    let previous = {
+        let mut guard = MOTION_REFERENCE.write();
+        guard.replace(current.clone())
+    };
+
+    let previous = match previous {
+        Some(p) if p.dimensions() == current.dimensions() => p,
+        _ => return Ok(0.0),
+    };
+
+    let noise_floor = (threshold * 255.0) as u8;
+    let total: u64 = current
+        .as_raw()
+        .iter()
+        .zip(previous.as_raw().iter())
+        .map(|(&a, &b)| a.abs_diff(b))
+        .filter(|&diff| diff > noise_floor)
+        .map(|diff| diff as u64)
+        .sum();
+
+    let pixels = current.as_raw().len().max(1) as f32;
+    Ok((total as f32 / (pixels * 255.0)).clamp(0.0, 1.0))
+}
+
+/// Measure brightness, edges and paper coverage inside a rectangle of the current frame
+/// The rectangle is in full-frame pixels and must lie within the frame. A low fill ratio
+/// suggests moving closer; high edge density with low fill often means clutter or blur.
+#[tauri::command]
+pub async fn analyze_roi(x: u32, y: u32, width: u32, height: u32) -> Result<RoiStats, AppError> {
+    let frame = latest_frame_rgb()?;
+    let (frame_w, frame_h) = frame.dimensions();
+    let fits = width > 0
+        && height > 0
+        && x.checked_add(width).is_some_and(|right| right <= frame_w)
+        && y.checked_add(height)
+            .is_some_and(|bottom| bottom <= frame_h);
+    if !fits {
+        return Err(AppError::InvalidArgument(format!(
+            "Region {}x{} at ({}, {}) is outside the {}x{} frame",
+            width, height, x, y, frame_w, frame_h
+        )));
+    }
+
+    let region = image::imageops::crop_imm(&frame, x, y, width, height).to_image();
+    let gray = DynamicImage::ImageRgb8(region.clone()).to_luma8();
+    let pixels = (width * height) as f32;
+
+    let luma_total: u64 = gray.as_raw().iter().map(|&l| l as u64).sum();
+    let paper = region
+        .pixels()
+        .zip(gray.pixels())
+        .filter(|(Rgb(rgb), luma)| {
+            let chroma = rgb.iter().max().unwrap_or(&0) - rgb.iter().min().unwrap_or(&0);
+            luma.0[0] >= ROI_PAPER_MIN_LUMA && chroma <= ROI_PAPER_MAX_CHROMA
+        })
+        .count();
+
+    // Sobel over the interior; the one-pixel border has no full neighbourhood
+    let mut edges = 0usize;
+    let mut edge_samples = 0usize;
+    if width >= 3 && height >= 3 {
+        let at = |px: u32, py: u32| gray.get_pixel(px, py).0[0] as f32;
+        for py in 1..height - 1 {
+            for px in 1..width - 1 {
+                let gx = at(px + 1, py - 1) + 2.0 * at(px + 1, py) + at(px + 1, py + 1)
+                    - at(px - 1, py - 1)
+                    - 2.0 * at(px - 1, py)
+                    - at(px - 1, py + 1);
+                let gy = at(px - 1, py + 1) + 2.0 * at(px, py + 1) + at(px + 1, py + 1)
+                    - at(px - 1, py - 1)
+                    - 2.0 * at(px, py - 1)
+                    - at(px + 1, py - 1);
+                if (gx * gx + gy * gy).sqrt() > ROI_EDGE_THRESHOLD {
+                    edges += 1;
+                }
+                edge_samples += 1;
+            }
+        }
+    }
+
+    Ok(RoiStats {
+        mean_brightness: luma_total as f32 / (pixels * 255.0),
+        edge_density: edges as f32 / edge_samples.max(1) as f32,
+        fill_ratio: paper as f32 / pixels,
+    })
+}
+
+/// Start saving a frame from the stream every `interval_ms` into `dest_dir`
+#[tauri::command]
+pub async fn start_timelapse(
+    app: AppHandle,
+    interval_ms: u64,
+    dest_dir: String,
+) -> Result<String, AppError> {
+    if TIMELAPSE_RUNNING.load(Ordering::SeqCst) {
+        return Ok("Timelapse already running".to_string());
+    }
+
+    if interval_ms < MIN_TIMELAPSE_INTERVAL_MS {
+        return Err(AppError::InvalidArgument(format!(
+            "Timelapse interval must be at least {} ms",
+            MIN_TIMELAPSE_INTERVAL_MS
+        )));
+    }
+
+    let dir = PathBuf::from(&dest_dir);
+    fs::create_dir_all(&dir)
+        .map_err(|e| AppError::IoError(format!("Failed to create timelapse directory: {}", e)))?;
+
+    STOP_TIMELAPSE.store(false, Ordering::SeqCst);
+    TIMELAPSE_RUNNING.store(true, Ordering::SeqCst);
+
+    let app_handle = app.clone();
+    thread::spawn(move || {
+        run_timelapse(app_handle, Duration::from_millis(interval_ms), dir);
+    });
+
+    Ok("Timelapse started".to_string())
+}
+
+/// Stop the running timelapse
+#[tauri::command]
+pub async fn stop_timelapse() -> Result<String, AppError> {
+    if !TIMELAPSE_RUNNING.load(Ordering::SeqCst) {
+        return Ok("Timelapse not running".to_string());
+    }
+
+    STOP_TIMELAPSE.store(true, Ordering::SeqCst);
+
+    // Wait for the timelapse thread to finish (with timeout)
+    let mut attempts = 0;
+    while TIMELAPSE_RUNNING.load(Ordering::SeqCst) && attempts < 50 {
+        thread::sleep(Duration::from_millis(50));
+        attempts += 1;
+    }
+
+    if TIMELAPSE_RUNNING.load(Ordering::SeqCst) {
+        return Err("Timelapse failed to stop in time".into());
+    }
+
+    Ok("Timelapse stopped".to_string())
+}
+
+/// Start saving stream frames as `frame_000001.png`, `frame_000002.png`, ... into `dest_dir`
+/// `fps` (1 up to the stream rate) sets how often a frame is taken; existing files with
+/// the same names are overwritten. Emits `png-sequence-progress` for each saved frame.
+#[tauri::command]
+pub async fn start_png_sequence(
+    app: AppHandle,
+    dest_dir: String,
+    fps: u32,
+) -> Result<String, AppError> {
+    if PNG_SEQUENCE_RUNNING.load(Ordering::SeqCst) {
+        return Ok("PNG sequence already running".to_string());
+    }
+
+    if fps == 0 || fps as u64 > TARGET_FPS {
+        return Err(AppError::InvalidArgument(format!(
+            "PNG sequence fps must be between 1 and {}",
+            TARGET_FPS
+        )));
+    }
+
+    let dir = PathBuf::from(&dest_dir);
+    fs::create_dir_all(&dir).map_err(|e| {
+        AppError::IoError(format!("Failed to create PNG sequence directory: {}", e))
+    })?;
+
+    STOP_PNG_SEQUENCE.store(false, Ordering::SeqCst);
+    PNG_SEQUENCE_RUNNING.store(true, Ordering::SeqCst);
+
+    let app_handle = app.clone();
+    thread::spawn(move || {
+        run_png_sequence(app_handle, Duration::from_secs(1) / fps, dir);
+    });
+
+    Ok("PNG sequence started".to_string())
+}
+
+/// Stop the running PNG sequence
+#[tauri::command]
+pub async fn stop_png_sequence() -> Result<String, AppError> {
+    if !PNG_SEQUENCE_RUNNING.load(Ordering::SeqCst) {
+        return Ok("PNG sequence not running".to_string());
+    }
+
+    STOP_PNG_SEQUENCE.store(true, Ordering::SeqCst);
+
+    // Wait for the sequence thread to finish (with timeout)
+    let mut attempts = 0;
+    while PNG_SEQUENCE_RUNNING.load(Ordering::SeqCst) && attempts < 50 {
+        thread::sleep(Duration::from_millis(50));
+        attempts += 1;
+    }
+
+    if PNG_SEQUENCE_RUNNING.load(Ordering::SeqCst) {
+        return Err("PNG sequence failed to stop in time".into());
+    }
+
+    Ok("PNG sequence stopped".to_string())
+}
+
+/// Keep the last `seconds` of frames, sampled at `fps`, in memory for dump_ringbuffer
+/// Restarting clears the buffer; stopping keeps it so it can still be dumped.
+#[tauri::command]
+pub async fn start_frame_ringbuffer(seconds: u32, fps: u32) -> Result<String, AppError> {
+    if RINGBUFFER_RUNNING.load(Ordering::SeqCst) {
+        return Ok("Frame ring buffer already running".to_string());
+    }
+
+    if fps == 0 || fps as u64 > TARGET_FPS {
+        return Err(AppError::InvalidArgument(format!(
+            "Ring buffer fps must be between 1 and {}",
+            TARGET_FPS
+        )));
+    }
+    let capacity = seconds.saturating_mul(fps);
+    if capacity == 0 || capacity > MAX_RINGBUFFER_FRAMES {
+        return Err(AppError::InvalidArgument(format!(
+            "Ring buffer must hold between 1 and {} frames (seconds x fps)",
+            MAX_RINGBUFFER_FRAMES
+        )));
+    }
+
+    FRAME_RING.lock().clear();
+    STOP_RINGBUFFER.store(false, Ordering::SeqCst);
+    RINGBUFFER_RUNNING.store(true, Ordering::SeqCst);
+
+    thread::spawn(move || {
+        run_frame_ringbuffer(Duration::from_secs(1) / fps, capacity as usize);
+    });
+
+    Ok("Frame ring buffer started".to_string())
+}
+
+/// Stop filling the frame ring buffer
+#[tauri::command]
+pub async fn stop_frame_ringbuffer() -> Result<String, AppError> {
+    if !RINGBUFFER_RUNNING.load(Ordering::SeqCst) {
+        return Ok("Frame ring buffer not running".to_string());
+    }
+
+    STOP_RINGBUFFER.store(true, Ordering::SeqCst);
+
+    // Wait for the ring buffer thread to finish (with timeout)
+    let mut attempts = 0;
+    while RINGBUFFER_RUNNING.load(Ordering::SeqCst) && attempts < 50 {
+        thread::sleep(Duration::from_millis(50));
+        attempts += 1;
+    }
+
+    if RINGBUFFER_RUNNING.load(Ordering::SeqCst) {
+        return Err("Frame ring buffer failed to stop in time".into());
+    }
+
+    Ok("Frame ring buffer stopped".to_string())
+}
+
+/// Write the buffered frames to `dest_dir` as a PNG sequence, oldest first
+/// Files are named event_<timestamp>_NNNN.png so repeated dumps don't overwrite each other.
+/// The buffer keeps filling while the dump is written.
+#[tauri::command]
+pub async fn dump_ringbuffer(dest_dir: String) -> Result<Vec<String>, AppError> {
+    // Snapshot the ring; frames are shared, so this doesn't hold the lock while encoding
+    let frames: Vec<Arc<RgbImage>> = FRAME_RING.lock().iter().cloned().collect();
+    if frames.is_empty() {
+        return Err("Frame ring buffer is empty".into());
+    }
+
+    let dir = PathBuf::from(&dest_dir);
+    fs::create_dir_all(&dir)
+        .map_err(|e| AppError::IoError(format!("Failed to create dump directory: {}", e)))?;
+    let prefix = format!("event_{}", Local::now().format("%Y%m%d_%H%M%S"));
+
+    tauri::async_runtime::spawn_blocking(move || {
+        frames
+            .iter()
+            .enumerate()
+            .map(|(i, frame)| {
+                let filepath = dir.join(format!("{}_{:04}.png", prefix, i + 1));
+                frame
+                    .save_with_format(&filepath, ImageFormat::Png)
+                    .map_err(|e| {
+                        AppError::IoError(format!("Failed to save ring buffer frame: {}", e))
+                    })?;
+                Ok(filepath.to_string_lossy().to_string())
+            })
+            .collect()
+    })
+    .await
+    .map_err(|e| format!("Failed to dump ring buffer: {}", e))?
+}
+
+/// Capture a burst of `frames` at `fps` and save it as a looping animated GIF
+/// Frames are reduced to GIF_MAX_SIZE on their longer side. If `dest` is an existing
+/// directory the GIF is saved inside it as GIF_<timestamp>.gif. Returns the path written.
+#[tauri::command]
+pub async fn capture_gif(frames: u32, fps: u32, dest: String) -> Result<String, AppError> {
+    if frames == 0 || frames > MAX_GIF_FRAMES {
+        return Err(AppError::InvalidArgument(format!(
+            "GIF frame count must be between 1 and {}",
+            MAX_GIF_FRAMES
+        )));
+    }
+    if fps == 0 || fps as u64 > TARGET_FPS {
+        return Err(AppError::InvalidArgument(format!(
+            "GIF fps must be between 1 and {}",
+            TARGET_FPS
+        )));
+    }
+    if !CAMERA_RUNNING.load(Ordering::SeqCst) || CAMERA_PAUSED.load(Ordering::SeqCst) {
+        return Err(AppError::CameraNotInitialized(no_frame_error()));
+    }
+
+    let mut target = PathBuf::from(&dest);
+    if target.is_dir() {
+        let stem = format!("GIF_{}", Local::now().format("%Y%m%d_%H%M%S"));
+        target = unique_path(&target, &stem, "gif");
+    }
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| AppError::IoError(format!("Failed to create GIF directory: {}", e)))?;
+    }
+
+    let interval = Duration::from_secs(1) / fps;
+    let burst = tauri::async_runtime::spawn_blocking(move || capture_burst(frames, interval))
+        .await
+        .map_err(|e| format!("Failed to capture GIF frames: {}", e))??;
+
+    let output = target.clone();
+    tauri::async_runtime::spawn_blocking(move || encode_gif(&burst, fps, &output))
+        .await
+        .map_err(|e| format!("Failed to encode GIF: {}", e))??;
+    Ok(target.to_string_lossy().to_string())
+}
+
+/// Take `count` stream frames `interval` apart, reduced for a GIF
+fn capture_burst(count: u32, interval: Duration) -> Result<Vec<RgbImage>, AppError> {
+    let mut frames = Vec::with_capacity(count as usize);
+    let mut next_capture = Instant::now();
+    for _ in 0..count {
+        if STOP_CAMERA_TASKS.load(Ordering::SeqCst) {
+            return Err(AppError::CameraNotInitialized("Camera stopped".to_string()));
+        }
+        thread::sleep(next_capture.saturating_duration_since(Instant::now()));
+        next_capture += interval;
+
+        let frame = latest_frame_rgb()?;
+        let frame = if frame.width().max(frame.height()) > GIF_MAX_SIZE {
+            fit_within(&frame, GIF_MAX_SIZE)
+        } else {
+            frame
+        };
+        frames.push(frame);
+    }
+    Ok(frames)
+}
+
+/// Write frames to `path` as an endlessly looping GIF shown at `fps`
+fn encode_gif(frames: &[RgbImage], fps: u32, path: &Path) -> Result<(), AppError> {
+    let file = fs::File::create(path)
+        .map_err(|e| AppError::IoError(format!("Failed to create GIF: {}", e)))?;
+    let mut encoder = GifEncoder::new_with_speed(std::io::BufWriter::new(file), GIF_ENCODE_SPEED);
+    encoder
+        .set_repeat(Repeat::Infinite)
+        .map_err(|e| format!("Failed to encode GIF: {}", e))?;
+
+    let delay = Delay::from_numer_denom_ms(1000, fps);
+    let gif_frames = frames.iter().map(|frame| {
+        let rgba = DynamicImage::ImageRgb8(frame.clone()).into_rgba8();
+        Frame::from_parts(rgba, 0, 0, delay)
+    });
+    encoder
+        .encode_frames(gif_frames)
+        .map_err(|e| format!("Failed to encode GIF: {}", e))?;
+    Ok(())
+}
+
+/// Remember a captured JPEG for get_last_frame and return it as a frame payload
+fn store_last_capture(jpeg: &[u8], width: u32, height: u32) -> CameraFrame {
+    let frame = CameraFrame {
+        data: format!("data:image/jpeg;base64,{}", STANDARD.encode(jpeg)),
+        width,
+        height,
+        format: "jpeg".to_string(),
+        stream: stream_kind(),
+    };
+    *LAST_CAPTURE.write() = Some(frame.clone());
+    frame
+}
+
+/// Emit a `capture-countdown` event each second; returns false if cancelled
+fn run_capture_countdown(app: &AppHandle, delay_ms: u64) -> bool {
+    let delay = Duration::from_millis(delay_ms);
+    let start_time = Instant::now();
+    let mut last_emitted = None;
+
+    while start_time.elapsed() < delay {
+        if CANCEL_CAPTURE.load(Ordering::SeqCst) || STOP_CAMERA_TASKS.load(Ordering::SeqCst) {
+            return false;
+        }
+
+        let remaining_ms = delay.saturating_sub(start_time.elapsed()).as_millis() as u64;
+        let seconds_left = remaining_ms.div_ceil(1000);
+        if last_emitted != Some(seconds_left) {
+            let _ = app.emit(
+                "capture-countdown",
+                CaptureCountdown {
+                    seconds_left,
+                    remaining_ms,
+                },
+            );
+            last_emitted = Some(seconds_left);
+        }
+
+        thread::sleep(Duration::from_millis(50));
+    }
+
+    !CANCEL_CAPTURE.load(Ordering::SeqCst) && !STOP_CAMERA_TASKS.load(Ordering::SeqCst)
+}
+
+/// Take the self-timer shot, saving it to the gallery if asked
+fn timed_capture(app: &AppHandle, save: bool) -> Result<CaptureComplete, String> {
+    if !save {
+        let frame = latest_frame_rgb()?;
+        let jpeg = encode_jpeg(&frame)?;
+        return Ok(CaptureComplete {
+            frame: Some(store_last_capture(&jpeg, frame.width(), frame.height())),
+            image: None,
+        });
+    }
+
+    let saved = tauri::async_runtime::block_on(capture_photo(
+        app.clone(),
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+    ))?;
+    if !saved.success {
+        return Err(saved
+            .error
+            .unwrap_or_else(|| "Failed to save photo".to_string()));
+    }
+
+    Ok(CaptureComplete {
+        frame: None,
+        image: Some(new_gallery_image(Path::new(&saved.path))?),
+    })
+}
+
+/// Forget the last capture
+pub(crate) fn clear_last_capture() {
+    *LAST_CAPTURE.write() = None;
+}
+
+/// Drop the frames held for dump_ringbuffer
+pub(crate) fn clear_frame_ring() {
+    FRAME_RING.lock().clear();
+}
+
+/// Get a copy of the latest streamed frame as JPEG bytes
+fn latest_frame_jpeg() -> Option<Vec<u8>> {
+    let frame = {
+        let guard = LATEST_FRAME.read();
+        guard.clone()
+    };
+
+    // YUYV passthrough and downscaled previews keep no full-size JPEG; encode one
+    frame.or_else(|| {
+        latest_frame_rgb()
+            .ok()
+            .and_then(|img| encode_jpeg(&img).ok())
+    })
+}
+
+/// Get a copy of the latest streamed frame as RGB pixels
+pub(crate) fn latest_frame_rgb() -> Result<RgbImage, AppError> {
+    let frame = {
+        let guard = LATEST_RGB_FRAME.read();
+        guard.clone()
+    };
+
+    frame
+        .or_else(latest_yuyv_rgb)
+        .ok_or_else(|| AppError::CameraNotInitialized(no_frame_error()))
+}
+
+/// Why there's no frame to capture
+fn no_frame_error() -> String {
+    if CAMERA_PAUSED.load(Ordering::SeqCst) {
+        "Camera paused. Resume it before capturing".to_string()
+    } else {
+        "No frame available. Is the camera streaming?".to_string()
+    }
+}
+
+/// Drop the stored frames so nothing captures a stale picture
+fn clear_frame_buffers() {
+    {
+        let mut guard = LATEST_FRAME.write();
+        *guard = None;
+    }
+    {
+        let mut guard = LATEST_RGB_FRAME.write();
+        *guard = None;
+    }
+    {
+        let mut guard = LATEST_YUYV_FRAME.write();
+        *guard = None;
+    }
+}
+
+/// Convert the latest raw YUYV frame (if any) to RGB
+fn latest_yuyv_rgb() -> Option<RgbImage> {
+    let guard = LATEST_YUYV_FRAME.read();
+    let frame = guard.as_ref()?;
+    let rgb = yuyv422_to_rgb(&frame.data, false)
+        .map_err(|e| eprintln!("Failed to convert YUYV frame: {}", e))
+        .ok()?;
+    RgbImage::from_raw(frame.width, frame.height, rgb)
+}
+
+/// Scale each channel so the frame averages out to gray, cancelling a colour cast
+fn apply_gray_world(img: &mut RgbImage) {
+    let mut sums = [0u64; 3];
+    for px in img.pixels() {
+        for c in 0..3 {
+            sums[c] += px[c] as u64;
+        }
+    }
+    if sums.contains(&0) {
+        return;
+    }
+
+    // Limit the gains so a frame that really is mostly one colour isn't wrecked
+    let gray = sums.iter().sum::<u64>() as f32 / 3.0;
+    let gains =
+        sums.map(|sum| (gray / sum as f32).clamp(1.0 / GRAY_WORLD_MAX_GAIN, GRAY_WORLD_MAX_GAIN));
+    for px in img.pixels_mut() {
+        for c in 0..3 {
+            px[c] = (px[c] as f32 * gains[c]).round().min(255.0) as u8;
+        }
+    }
+}
+
+/// Gamma-correct a frame so its mean luminance moves to AUTO_EXPOSURE_TARGET
+fn apply_auto_exposure(img: &mut RgbImage) {
+    let pixels = (img.width() as u64 * img.height() as u64).max(1);
+    let total: u64 = img
+        .pixels()
+        .map(|p| (299 * p[0] as u64 + 587 * p[1] as u64 + 114 * p[2] as u64) / 1000)
+        .sum();
+    let mean = (total as f32 / (pixels as f32 * 255.0)).clamp(0.01, 0.99);
+
+    if (mean - AUTO_EXPOSURE_TARGET).abs() <= AUTO_EXPOSURE_TOLERANCE {
+        return;
+    }
+
+    // mean^gamma = target, limited so near-black or blown-out frames aren't mangled
+    let gamma = (AUTO_EXPOSURE_TARGET.ln() / mean.ln())
+        .clamp(1.0 / AUTO_EXPOSURE_MAX_GAMMA, AUTO_EXPOSURE_MAX_GAMMA);
+
+    let mut lut = [0u8; 256];
+    for (value, out) in lut.iter_mut().enumerate() {
+        *out = ((value as f32 / 255.0).powf(gamma) * 255.0).round() as u8;
+    }
+    for pixel in img.pixels_mut() {
+        for channel in pixel.0.iter_mut() {
+            *channel = lut[*channel as usize];
+        }
+    }
+}
+
+/// Blend differently exposed frames of the same scene, per pixel, favouring whichever
+/// frames are well exposed there (the exposure fusion of Mertens et al., single scale)
+fn fuse_exposures(frames: &[RgbImage]) -> RgbImage {
+    let (width, height) = frames[0].dimensions();
+    if frames
+        .iter()
+        .any(|frame| frame.dimensions() != (width, height))
+    {
+        return frames[frames.len() / 2].clone();
+    }
+
+    // Weight each pixel by how close its channels are to mid-grey
+    let weights: Vec<ImageBuffer<Luma<f32>, Vec<f32>>> = frames
+        .iter()
+        .map(|frame| {
+            let raw = ImageBuffer::from_fn(width, height, |x, y| {
+                let exposedness: f32 = frame
+                    .get_pixel(x, y)
+                    .0
+                    .iter()
+                    .map(|&c| {
+                        let d = c as f32 / 255.0 - 0.5;
+                        (-d * d / (2.0 * FUSION_SIGMA * FUSION_SIGMA)).exp()
+                    })
+                    .product();
+                Luma([exposedness + 1e-6])
+            });
+            image::imageops::fast_blur(&raw, FUSION_WEIGHT_BLUR)
+        })
+        .collect();
+
+    ImageBuffer::from_fn(width, height, |x, y| {
+        let mut sum = [0.0f32; 3];
+        let mut total = 0.0f32;
+        for (frame, weight) in frames.iter().zip(&weights) {
+            let w = weight.get_pixel(x, y)[0];
+            for (acc, &c) in sum.iter_mut().zip(frame.get_pixel(x, y).0.iter()) {
+                *acc += w * c as f32;
+            }
+            total += w;
+        }
+        Rgb(sum.map(|v| (v / total).round().clamp(0.0, 255.0) as u8))
+    })
+}
+
+/// Largest centered crop of `img` with a `ratio_w:ratio_h` shape
+fn crop_to_aspect(img: &RgbImage, ratio_w: u32, ratio_h: u32) -> RgbImage {
+    let (width, height) = img.dimensions();
+    let (crop_w, crop_h) = aspect_crop_size(width, height, ratio_w, ratio_h);
+
+    image::imageops::crop_imm(
+        img,
+        (width - crop_w) / 2,
+        (height - crop_h) / 2,
+        crop_w,
+        crop_h,
+    )
+    .to_image()
+}
+
+/// Size of the largest `ratio_w:ratio_h` rectangle that fits in a `width`x`height` frame
+pub(crate) fn aspect_crop_size(width: u32, height: u32, ratio_w: u32, ratio_h: u32) -> (u32, u32) {
+    // Too wide: keep full height; otherwise keep full width
+    let (crop_w, crop_h) = if width as u64 * ratio_h as u64 > height as u64 * ratio_w as u64 {
+        (
+            (height as u64 * ratio_w as u64 / ratio_h as u64) as u32,
+            height,
+        )
+    } else {
+        (
+            width,
+            (width as u64 * ratio_h as u64 / ratio_w as u64) as u32,
+        )
+    };
+    (crop_w.max(1), crop_h.max(1))
+}
+
+/// Resize a frame to `width`, keeping the aspect ratio
+fn scale_to_width(img: &RgbImage, width: u32) -> RgbImage {
+    let height = ((img.height() as u64 * width as u64) / img.width() as u64).max(1) as u32;
+    image::imageops::resize(img, width, height, FilterType::Triangle)
+}
+
+/// Resize a frame so its longer side is `max`, keeping the aspect ratio
+fn fit_within(img: &RgbImage, max: u32) -> RgbImage {
+    let (width, height) = img.dimensions();
+    let longer = width.max(height) as u64;
+    let new_width = ((width as u64 * max as u64) / longer).max(1) as u32;
+    let new_height = ((height as u64 * max as u64) / longer).max(1) as u32;
+    image::imageops::resize(img, new_width, new_height, FilterType::Triangle)
+}
+
+/// Encode an RGB image as PNG
+fn encode_png(img: &RgbImage) -> Result<Vec<u8>, String> {
+    let mut png_buffer = Cursor::new(Vec::new());
+    img.write_to(&mut png_buffer, ImageFormat::Png)
+        .map_err(|e| format!("Failed to encode PNG: {}", e))?;
+    Ok(png_buffer.into_inner())
+}
+
+/// Encode an RGB image as JPEG at the standard quality
+pub(crate) fn encode_jpeg(img: &RgbImage) -> Result<Vec<u8>, String> {
+    let mut jpeg_buffer = Cursor::new(Vec::new());
+    image::codecs::jpeg::JpegEncoder::new_with_quality(&mut jpeg_buffer, JPEG_QUALITY)
+        .encode_image(img)
+        .map_err(|e| format!("Failed to encode JPEG: {}", e))?;
+    Ok(jpeg_buffer.into_inner())
+}
+
+/// Alpha-blend a PNG watermark onto a corner of the frame, scaled to the frame width
+fn apply_watermark(
+    frame: &mut RgbImage,
+    watermark_path: &str,
+    position: WatermarkPosition,
+) -> Result<(), String> {
+    let watermark = image::open(watermark_path)
+        .map_err(|e| format!("Failed to load watermark: {}", e))?
+        .to_rgba8();
+
+    if watermark.width() == 0 || watermark.height() == 0 {
+        return Ok(());
+    }
+
+    // Scale to a fixed fraction of the frame width, keeping the aspect ratio
+    let target_width = ((frame.width() as f32 * WATERMARK_SCALE) as u32).max(1);
+    let target_height = ((watermark.height() as u64 * target_width as u64)
+        / watermark.width() as u64)
+        .max(1) as u32;
+    let watermark = image::imageops::resize(
+        &watermark,
+        target_width,
+        target_height,
+        FilterType::Triangle,
+    );
+
+    let x = match position {
+        WatermarkPosition::TopLeft | WatermarkPosition::BottomLeft => WATERMARK_MARGIN,
+        WatermarkPosition::TopRight | WatermarkPosition::BottomRight => frame
+            .width()
+            .saturating_sub(target_width + WATERMARK_MARGIN),
+    };
+    let y = match position {
+        WatermarkPosition::TopLeft | WatermarkPosition::TopRight => WATERMARK_MARGIN,
+        WatermarkPosition::BottomLeft | WatermarkPosition::BottomRight => frame
+            .height()
+            .saturating_sub(target_height + WATERMARK_MARGIN),
+    };
+
+    for (wx, wy, px) in watermark.enumerate_pixels() {
+        let (fx, fy) = (x + wx, y + wy);
+        if fx >= frame.width() || fy >= frame.height() {
+            continue;
+        }
+
+        let alpha = px[3] as f32 / 255.0;
+        let dst = frame.get_pixel_mut(fx, fy);
+        for c in 0..3 {
+            dst[c] = (px[c] as f32 * alpha + dst[c] as f32 * (1.0 - alpha)).round() as u8;
+        }
+    }
+
+    Ok(())
+}
+
+/// Draw `text` in a corner of the frame, white with a dark outline so it reads on any scene
+fn draw_timestamp(
+    frame: &mut RgbImage,
+    text: &str,
+    overlay: TimestampOverlay,
+) -> Result<(), String> {
+    let font = FontRef::try_from_slice(TIMESTAMP_FONT)
+        .map_err(|e| format!("Failed to load timestamp font: {}", e))?;
+    // Text taller than the frame can't be read anyway, and would take ages to draw
+    let size = overlay
+        .font_size
+        .unwrap_or(frame.height() as f32 * TIMESTAMP_SCALE)
+        .clamp(1.0, frame.height().max(1) as f32);
+    let scale = PxScale::from(size);
+    let (text_width, text_height) = text_size(scale, &font, text);
+
+    let position = overlay.position.unwrap_or(WatermarkPosition::BottomLeft);
+    let x = match position {
+        WatermarkPosition::TopLeft | WatermarkPosition::BottomLeft => WATERMARK_MARGIN,
+        WatermarkPosition::TopRight | WatermarkPosition::BottomRight => {
+            frame.width().saturating_sub(text_width + WATERMARK_MARGIN)
+        }
+    } as i32;
+    let y = match position {
+        WatermarkPosition::TopLeft | WatermarkPosition::TopRight => WATERMARK_MARGIN,
+        WatermarkPosition::BottomLeft | WatermarkPosition::BottomRight => frame
+            .height()
+            .saturating_sub(text_height + WATERMARK_MARGIN),
+    } as i32;
+
+    let outline = ((size / 16.0).ceil() as i32).min(TIMESTAMP_MAX_OUTLINE);
+    for dy in -outline..=outline {
+        for dx in -outline..=outline {
+            if dx != 0 || dy != 0 {
+                draw_text_mut(frame, Rgb([0, 0, 0]), x + dx, y + dy, scale, &font, text);
+            }
+        }
+    }
+    draw_text_mut(frame, Rgb([255, 255, 255]), x, y, scale, &font, text);
+    Ok(())
+}
+
+/// Internal function to run a timelapse: saves TL_000001.jpg, TL_000002.jpg, ...
+fn run_timelapse(app: AppHandle, interval: Duration, dir: PathBuf) {
+    let mut count: u64 = 0;
+    let mut next_capture = Instant::now();
+
+    loop {
+        if STOP_TIMELAPSE.load(Ordering::SeqCst) || STOP_CAMERA_TASKS.load(Ordering::SeqCst) {
+            break;
+        }
+
+        if Instant::now() >= next_capture {
+            next_capture += interval;
+
+            match latest_frame_jpeg() {
+                Some(jpeg) => {
+                    let filepath = dir.join(format!("TL_{:06}.jpg", count + 1));
+                    match fs::write(&filepath, jpeg) {
+                        Ok(()) => {
+                            count += 1;
+                            let _ = app.emit(
+                                "timelapse-frame",
+                                TimelapseFrame {
+                                    count,
+                                    path: filepath.to_string_lossy().to_string(),
+                                },
+                            );
+                        }
+                        Err(e) => {
+                            eprintln!("Failed to save timelapse frame: {}", e);
+                        }
+                    }
+                }
+                None => {
+                    eprintln!("Timelapse: no frame available, skipping");
+                }
+            }
+        }
+
+        // Sleep in short steps so stop requests are handled promptly
+        thread::sleep(Duration::from_millis(50));
+    }
+
+    TIMELAPSE_RUNNING.store(false, Ordering::SeqCst);
+    STOP_TIMELAPSE.store(false, Ordering::SeqCst);
+}
+
+/// PNG sequence loop: saves the latest RGB frame every `interval`
+/// Only reads the shared frame buffer, so the stream and other captures carry on alongside.
+fn run_png_sequence(app: AppHandle, interval: Duration, dir: PathBuf) {
+    let mut count: u64 = 0;
+    let mut next_capture = Instant::now();
+
+    loop {
+        if STOP_PNG_SEQUENCE.load(Ordering::SeqCst) || STOP_CAMERA_TASKS.load(Ordering::SeqCst) {
+            break;
+        }
+
+        let now = Instant::now();
+        if now >= next_capture {
+            // Skip slots missed while encoding rather than bursting to catch up
+            next_capture += interval;
+            if next_capture < now {
+                next_capture = now + interval;
+            }
+
+            match latest_frame_rgb() {
+                Ok(frame) => {
+                    let filepath = dir.join(format!("frame_{:06}.png", count + 1));
+                    match frame.save_with_format(&filepath, ImageFormat::Png) {
+                        Ok(()) => {
+                            count += 1;
+                            let _ = app.emit(
+                                "png-sequence-progress",
+                                PngSequenceProgress {
+                                    count,
+                                    path: filepath.to_string_lossy().to_string(),
+                                },
+                            );
+                        }
+                        Err(e) => {
+                            eprintln!("Failed to save PNG sequence frame: {}", e);
+                        }
+                    }
+                }
+                Err(e) => {
+                    eprintln!("PNG sequence: {}, skipping", e);
+                }
+            }
+        }
+
+        // Sleep in short steps so stop requests are handled promptly
+        let wait = next_capture.saturating_duration_since(Instant::now());
+        thread::sleep(wait.min(Duration::from_millis(50)));
+    }
+
+    PNG_SEQUENCE_RUNNING.store(false, Ordering::SeqCst);
+    STOP_PNG_SEQUENCE.store(false, Ordering::SeqCst);
+}
+
+/// Ring buffer loop: copies the latest frame into FRAME_RING at each interval
+fn run_frame_ringbuffer(interval: Duration, capacity: usize) {
+    let mut next_capture = Instant::now();
+
+    loop {
+        if STOP_RINGBUFFER.load(Ordering::SeqCst) || STOP_CAMERA_TASKS.load(Ordering::SeqCst) {
+            break;
+        }
+
+        let now = Instant::now();
+        if now >= next_capture {
+            next_capture += interval;
+            if next_capture < now {
+                next_capture = now + interval;
+            }
+
+            // No frame while the stream is paused or starting; just leave a gap
+            if let Ok(frame) = latest_frame_rgb() {
+                let mut ring = FRAME_RING.lock();
+                if ring.len() >= capacity {
+                    ring.pop_front();
+                }
+                ring.push_back(Arc::new(frame));
+            }
+        }
+
+        // Sleep in short steps so stop requests are handled promptly
+        let wait = next_capture.saturating_duration_since(Instant::now());
+        thread::sleep(wait.min(Duration::from_millis(50)));
+    }
+
+    RINGBUFFER_RUNNING.store(false, Ordering::SeqCst);
+    STOP_RINGBUFFER.store(false, Ordering::SeqCst);
+}
+
+/// Resets the stream flags and frame buffers when the stream thread exits
+/// Runs on normal return and on panic, so a nokhwa panic can't leave
+/// CAMERA_RUNNING stuck and block every later start_camera_stream.
+struct StreamStateGuard;
+
+impl Drop for StreamStateGuard {
+    fn drop(&mut self) {
+        clear_frame_buffers();
+        {
+            let mut guard = CAMERA_FORMAT.write();
+            *guard = None;
+        }
+        {
+            let mut guard = MOTION_REFERENCE.write();
+            *guard = None;
+        }
+        {
+            let mut guard = PREVIEW_COUNTERS.write();
+            *guard = PreviewCounters::default();
+        }
+
+        CAMERA_RUNNING.store(false, Ordering::SeqCst);
+        STOP_SIGNAL.store(false, Ordering::SeqCst);
+        PAUSE_REQUESTED.store(false, Ordering::SeqCst);
+        CAMERA_PAUSED.store(false, Ordering::SeqCst);
+        SOFTWARE_WHITE_BALANCE.store(false, Ordering::SeqCst);
+        INFRARED_STREAM.store(false, Ordering::SeqCst);
+        WHITE_BALANCE_REQUEST.lock().take();
+        HDR_REQUEST.lock().take();
+        LATENCY_REQUEST.lock().take();
+        CONTROL_RANGES_REQUEST.lock().take();
+    }
+}
+
+/// Store and emit a raw YUYV frame without decoding it
+fn emit_yuyv_frame(app: &AppHandle, frame: &Buffer) {
+    let resolution = frame.resolution();
+    let data = frame.buffer().to_vec();
+    let base64_data = STANDARD.encode(&data);
+
+    {
+        let mut guard = LATEST_YUYV_FRAME.write();
+        *guard = Some(YuyvFrame {
+            width: resolution.width(),
+            height: resolution.height(),
+            data,
+        });
+    }
+
+    let _ = app.emit(
+        "camera-frame",
+        CameraFrame {
+            data: base64_data,
+            width: resolution.width(),
+            height: resolution.height(),
+            format: "yuyv".to_string(),
+            stream: "color".to_string(),
+        },
+    );
+}
+
+/// Width to send the preview at: `preview_width` from start_camera_stream, else the
+/// configured scale
+fn preview_target_width(width: u32, preview_width: Option<u32>, config: PreviewConfig) -> u32 {
+    preview_width.unwrap_or_else(|| ((width as f32 * config.scale).round() as u32).max(1))
+}
+
+/// Downscale, encode and emit a grayscale infrared frame, keeping it for capture
+/// Captures and image analysis see it as RGB with equal channels.
+fn emit_infrared_frame(
+    app: &AppHandle,
+    gray: GrayImage,
+    preview_width: Option<u32>,
+    config: PreviewConfig,
+) -> Result<(), String> {
+    let (width, height) = gray.dimensions();
+    let target_width = preview_target_width(width, preview_width, config);
+    let preview = (target_width < width).then(|| {
+        let target_height = ((height as u64 * target_width as u64) / width as u64).max(1) as u32;
+        image::imageops::resize(&gray, target_width, target_height, FilterType::Triangle)
+    });
+    let preview_img = preview.as_ref().unwrap_or(&gray);
+
+    let mut jpeg_buffer = Cursor::new(Vec::new());
+    image::codecs::jpeg::JpegEncoder::new_with_quality(&mut jpeg_buffer, config.jpeg_quality)
+        .encode_image(preview_img)
+        .map_err(|e| format!("Failed to encode infrared frame: {}", e))?;
+    let (preview_w, preview_h) = preview_img.dimensions();
+
+    {
+        let mut guard = LATEST_FRAME.write();
+        *guard = None;
+    }
+    {
+        let mut guard = LATEST_RGB_FRAME.write();
+        *guard = Some(DynamicImage::ImageLuma8(gray).to_rgb8());
+    }
+
+    let base64_data = STANDARD.encode(jpeg_buffer.into_inner());
+    let _ = app.emit(
+        "camera-frame",
+        CameraFrame {
+            data: format!("data:image/jpeg;base64,{}", base64_data),
+            width: preview_w,
+            height: preview_h,
+            format: "jpeg".to_string(),
+            stream: "infrared".to_string(),
+        },
+    );
+    Ok(())
+}
+
+/// "infrared" while the stream delivers infrared frames, otherwise "color"
+pub(crate) fn stream_kind() -> String {
+    if INFRARED_STREAM.load(Ordering::SeqCst) {
+        "infrared".to_string()
+    } else {
+        "color".to_string()
+    }
+}
+
+/// Ask for `resolution`, preferring MJPEG for RGB, raw YUYV for passthrough, or grayscale
+/// for infrared
+pub(crate) fn requested_format(
+    pixel_format: StreamPixelFormat,
+    resolution: Resolution,
+) -> RequestedFormat<'static> {
+    let requested_type = |frame_format| {
+        RequestedFormatType::Closest(CameraFormat::new(
+            resolution,
+            frame_format,
+            TARGET_FPS as u32,
+        ))
+    };
+    match pixel_format {
+        StreamPixelFormat::Rgb => {
+            RequestedFormat::new::<RgbFormat>(requested_type(FrameFormat::MJPEG))
+        }
+        StreamPixelFormat::Yuyv => {
+            RequestedFormat::new::<YuyvFormat>(requested_type(FrameFormat::YUYV))
+        }
+        StreamPixelFormat::Infrared => {
+            RequestedFormat::new::<LumaFormat>(requested_type(FrameFormat::GRAY))
+        }
+    }
+}
+
+/// Describe the format an opened camera negotiated
+fn format_info(camera: &Camera) -> CameraFormatInfo {
+    let format = camera.camera_format();
+    CameraFormatInfo {
+        width: format.width(),
+        height: format.height(),
+        frame_rate: format.frame_rate(),
+        pixel_format: format.format().to_string(),
+        backend: active_backend(camera.backend()).to_string(),
+    }
+}
+
+/// Open the camera at `index` and start its stream
+pub(crate) fn open_camera(
+    index: u32,
+    requested: RequestedFormat,
+    backend: ApiBackend,
+) -> Result<Camera, String> {
+    let mut camera = Camera::with_backend(CameraIndex::Index(index), requested, backend)
+        .map_err(|e| format!("Failed to open camera: {}", e))?;
+    camera
+        .open_stream()
+        .map_err(|e| format!("Failed to start camera stream: {}", e))?;
+    Ok(camera)
+}
+
+/// Resolution used when the caller doesn't ask for one
+pub(crate) fn default_resolution() -> Resolution {
+    Resolution::new(CAMERA_WIDTH, CAMERA_HEIGHT)
+}
+
+/// The requested resolution followed by the standard modes smaller than it
+fn resolution_chain(requested: Resolution) -> Vec<Resolution> {
+    let pixels = |r: Resolution| r.width() as u64 * r.height() as u64;
+    let mut chain = vec![requested];
+    chain.extend(
+        FALLBACK_RESOLUTIONS
+            .iter()
+            .map(|&(w, h)| Resolution::new(w, h))
+            .filter(|&r| pixels(r) < pixels(requested)),
+    );
+    chain
+}
+
+/// Open camera 0, stepping down the resolution chain until a stream opens
+/// Emits `camera-fallback` before each step down; returns the last error if none open.
+fn open_with_fallback(app: &AppHandle, options: &StreamOptions) -> Result<Camera, String> {
+    let chain = resolution_chain(options.resolution);
+    let mut last_error = String::new();
+    for (i, &resolution) in chain.iter().enumerate() {
+        let requested = requested_format(options.pixel_format, resolution);
+        match open_camera(0, requested, options.backend) {
+            Ok(camera) => return Ok(camera),
+            // No resolution helps a camera without a grayscale format
+            Err(_)
+                if options.pixel_format == StreamPixelFormat::Infrared
+                    && has_infrared_stream(0, options.backend) == Ok(false) =>
+            {
+                return Err(
+                    "Camera has no infrared stream (it only offers color formats)".to_string(),
+                );
+            }
+            Err(message) => {
+                if let Some(next) = chain.get(i + 1) {
+                    let _ = app.emit(
+                        "camera-fallback",
+                        CameraFallback {
+                            from_width: resolution.width(),
+                            from_height: resolution.height(),
+                            to_width: next.width(),
+                            to_height: next.height(),
+                            error: message.clone(),
+                        },
+                    );
+                }
+                last_error = message;
+            }
+        }
+    }
+    Err(last_error)
+}
+
+/// Whether the camera offers the 8-bit grayscale format infrared streams use
+fn has_infrared_stream(index: u32, backend: ApiBackend) -> Result<bool, String> {
+    let requested = RequestedFormat::new::<RgbFormat>(RequestedFormatType::None);
+    let mut camera = Camera::with_backend(CameraIndex::Index(index), requested, backend)
+        .map_err(|e| format!("Failed to open camera: {}", e))?;
+    let formats = camera
+        .compatible_camera_formats()
+        .map_err(|e| format!("Failed to query camera formats: {}", e))?;
+    Ok(formats
+        .iter()
+        .any(|format| format.format() == FrameFormat::GRAY))
+}
+
+/// Look for another process with /dev/video<index> open
+#[cfg(target_os = "linux")]
+fn device_open_elsewhere(index: u32) -> Option<bool> {
+    let device = PathBuf::from(format!("/dev/video{}", index));
+    let own_pid = std::process::id().to_string();
+    let processes = fs::read_dir("/proc").ok()?;
+
+    let held = processes.flatten().any(|process| {
+        let name = process.file_name().to_string_lossy().to_string();
+        if name == own_pid || !name.chars().all(|c| c.is_ascii_digit()) {
+            return false;
+        }
+        // Processes we can't inspect are skipped
+        fs::read_dir(process.path().join("fd"))
+            .into_iter()
+            .flatten()
+            .flatten()
+            .any(|fd| fs::read_link(fd.path()).is_ok_and(|target| target == device))
+    });
+    Some(held)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn device_open_elsewhere(_index: u32) -> Option<bool> {
+    None
+}
+
+/// Map a backend name to nokhwa's API, using auto for unknown or unavailable backends
+fn parse_camera_backend(name: Option<&str>) -> ApiBackend {
+    let name = match name {
+        Some(name) => name.trim().to_lowercase(),
+        None => return ApiBackend::Auto,
+    };
+
+    let backend = match name.as_str() {
+        "auto" => return ApiBackend::Auto,
+        "v4l2" | "v4l" | "video4linux" => ApiBackend::Video4Linux,
+        "avfoundation" => ApiBackend::AVFoundation,
+        "mediafoundation" | "msmf" => ApiBackend::MediaFoundation,
+        _ => {
+            eprintln!("Unknown camera backend {:?}, using auto", name);
+            return ApiBackend::Auto;
+        }
+    };
+
+    // Only the platform's native backend is built in
+    if native_api_backend() != Some(backend) {
+        eprintln!(
+            "Camera backend {} is not available here, using auto",
+            backend
+        );
+        return ApiBackend::Auto;
+    }
+    backend
+}
+
+/// The backend actually in use; auto always resolves to the native one
+fn active_backend(backend: ApiBackend) -> ApiBackend {
+    match backend {
+        ApiBackend::Auto => native_api_backend().unwrap_or(ApiBackend::Auto),
+        other => other,
+    }
+}
+
+/// Record one preview frame (`None` if it was dropped) and forget old ones
+fn record_preview_frame(encode_time: Option<Duration>) {
+    let now = Instant::now();
+    let mut counters = PREVIEW_COUNTERS.write();
+    counters.frames.push_back((now, encode_time));
+    while counters
+        .frames
+        .front()
+        .is_some_and(|(at, _)| now.duration_since(*at) > PREVIEW_STATS_WINDOW)
+    {
+        counters.frames.pop_front();
+    }
+}
+
+/// Sleep for `delay` in short steps; returns false if the stream was stopped meanwhile
+fn wait_unless_stopped(delay: Duration) -> bool {
+    let deadline = Instant::now() + delay;
+    while Instant::now() < deadline {
+        if STOP_SIGNAL.load(Ordering::SeqCst) {
+            return false;
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+    !STOP_SIGNAL.load(Ordering::SeqCst)
+}
+
+/// Set white balance on the camera, falling back to software correction if it can't
+fn apply_white_balance(camera: &mut Camera, setting: WhiteBalanceSetting) -> WhiteBalanceApplied {
+    let hardware = match setting.kelvin() {
+        Some(kelvin) => set_white_balance_temperature(camera, kelvin).map(Some),
+        None => camera
+            .set_camera_control(
+                KnownCameraControl::Other(AUTO_WHITE_BALANCE_CONTROL),
+                ControlValueSetter::Boolean(true),
+            )
+            .map(|_| None)
+            .map_err(|e| format!("Failed to enable auto white balance: {}", e)),
+    };
+
+    match hardware {
+        Ok(kelvin) => {
+            SOFTWARE_WHITE_BALANCE.store(false, Ordering::SeqCst);
+            WhiteBalanceApplied {
+                method: "hardware".to_string(),
+                kelvin,
+            }
+        }
+        Err(e) => {
+            eprintln!("Using software white balance: {}", e);
+            SOFTWARE_WHITE_BALANCE.store(true, Ordering::SeqCst);
+            WhiteBalanceApplied {
+                method: "software".to_string(),
+                kelvin: None,
+            }
+        }
+    }
+}
+
+/// Set a manual colour temperature, snapped to the range and step the camera reports
+fn set_white_balance_temperature(camera: &mut Camera, kelvin: u32) -> Result<i64, String> {
+    // Manual temperature is ignored (or rejected) while auto white balance is on
+    let _ = camera.set_camera_control(
+        KnownCameraControl::Other(AUTO_WHITE_BALANCE_CONTROL),
+        ControlValueSetter::Boolean(false),
+    );
+
+    let control = camera
+        .camera_control(KnownCameraControl::WhiteBalance)
+        .map_err(|e| format!("No white balance control: {}", e))?;
+    let value = match *control.description() {
+        ControlValueDescription::IntegerRange { min, max, step, .. } => {
+            let step = step.max(1);
+            min + (kelvin as i64).clamp(min, max).saturating_sub(min) / step * step
+        }
+        _ => kelvin as i64,
+    };
+
+    camera
+        .set_camera_control(
+            KnownCameraControl::WhiteBalance,
+            ControlValueSetter::Integer(value),
+        )
+        .map_err(|e| format!("Failed to set white balance: {}", e))?;
+    Ok(value)
+}
+
+/// Take one frame at each of three exposures around the current one, then restore it
+fn capture_exposure_bracket(camera: &mut Camera) -> Result<Vec<RgbImage>, String> {
+    let (control, description) = [
+        KnownCameraControl::Other(EXPOSURE_ABSOLUTE_CONTROL),
+        KnownCameraControl::Exposure,
+    ]
+    .into_iter()
+    .find_map(|control| {
+        let found = camera.camera_control(control).ok()?;
+        Some((control, found.description().clone()))
+    })
+    .ok_or("No exposure control")?;
+    let (min, max, step, value) = match description {
+        ControlValueDescription::IntegerRange {
+            min,
+            max,
+            step,
+            value,
+            ..
+        } => (min, max, step.max(1), value),
+        _ => return Err("Exposure control has no adjustable range".to_string()),
+    };
+
+    let snap = |v: i64| min + v.clamp(min, max).saturating_sub(min) / step * step;
+    let levels = [
+        snap(value / HDR_BRACKET_FACTOR),
+        snap(value),
+        snap(value.saturating_mul(HDR_BRACKET_FACTOR)),
+    ];
+    if levels[0] == levels[2] {
+        return Err("Exposure range too narrow to bracket".to_string());
+    }
+
+    // Manual exposure values are ignored while auto exposure is on
+    let auto_mode = camera
+        .camera_control(KnownCameraControl::Other(AUTO_EXPOSURE_MODE_CONTROL))
+        .ok()
+        .map(|c| c.value());
+    let _ = camera.set_camera_control(
+        KnownCameraControl::Other(AUTO_EXPOSURE_MODE_CONTROL),
+        ControlValueSetter::Integer(EXPOSURE_MODE_MANUAL),
+    );
+
+    let frames = levels
+        .iter()
+        .map(|&level| {
+            camera
+                .set_camera_control(control, ControlValueSetter::Integer(level))
+                .map_err(|e| format!("Failed to set exposure: {}", e))?;
+            settled_frame(camera)
+        })
+        .collect();
+
+    // Put the camera back the way it was, even if a capture failed
+    let _ = camera.set_camera_control(control, ControlValueSetter::Integer(value));
+    if let Some(mode) = auto_mode {
+        let _ =
+            camera.set_camera_control(KnownCameraControl::Other(AUTO_EXPOSURE_MODE_CONTROL), mode);
+    }
+    frames
+}
+
+/// Skip the frames captured while a control change takes effect and decode the next one
+fn settled_frame(camera: &mut Camera) -> Result<RgbImage, String> {
+    for _ in 0..HDR_SETTLE_FRAMES {
+        camera
+            .frame()
+            .map_err(|e| format!("Failed to capture frame: {}", e))?;
+    }
+    camera
+        .frame()
+        .and_then(|frame| frame.decode_image::<RgbFormat>())
+        .map_err(|e| format!("Failed to capture frame: {}", e))
+}
+
+/// Read the range of every standard control the camera supports
+fn read_control_ranges(camera: &Camera) -> Vec<CameraControlRange> {
+    all_known_camera_controls()
+        .into_iter()
+        .filter_map(|control| {
+            let found = camera.camera_control(control).ok()?;
+            let (min, max, step, default, value) = match *found.description() {
+                ControlValueDescription::IntegerRange {
+                    min,
+                    max,
+                    value,
+                    step,
+                    default,
+                } => (
+                    Some(min as f64),
+                    Some(max as f64),
+                    step as f64,
+                    default as f64,
+                    value as f64,
+                ),
+                ControlValueDescription::Integer {
+                    value,
+                    default,
+                    step,
+                } => (None, None, step as f64, default as f64, value as f64),
+                ControlValueDescription::FloatRange {
+                    min,
+                    max,
+                    value,
+                    step,
+                    default,
+                } => (Some(min), Some(max), step, default, value),
+                ControlValueDescription::Float {
+                    value,
+                    default,
+                    step,
+                } => (None, None, step, default, value),
+                ControlValueDescription::Boolean { value, default } => (
+                    Some(0.0),
+                    Some(1.0),
+                    1.0,
+                    f64::from(u8::from(default)),
+                    f64::from(u8::from(value)),
+                ),
+                _ => return None,
+            };
+            Some(CameraControlRange {
+                name: control_name(control),
+                min,
+                max,
+                step,
+                default,
+                value,
+            })
+        })
+        .collect()
+}
+
+/// snake_case name of a control, e.g. "backlight_comp"
+fn control_name(control: KnownCameraControl) -> String {
+    let mut name = String::new();
+    for (i, c) in control.to_string().chars().enumerate() {
+        if c.is_ascii_uppercase() && i > 0 {
+            name.push('_');
+        }
+        name.push(c.to_ascii_lowercase());
+    }
+    name
+}
+
+/// Time `samples` frame captures, after one untimed frame so a frame already queued
+/// by the driver isn't counted as instant
+fn time_frames(camera: &mut Camera, samples: u32) -> Result<Vec<Duration>, String> {
+    camera
+        .frame()
+        .map_err(|e| format!("Failed to capture frame: {}", e))?;
+    (0..samples)
+        .map(|_| {
+            let start = Instant::now();
+            camera
+                .frame()
+                .map(|_| start.elapsed())
+                .map_err(|e| format!("Failed to capture frame: {}", e))
+        })
+        .collect()
+}
+
+/// Internal function to run camera stream
+fn run_camera_stream(app: AppHandle, options: StreamOptions) {
+    CAMERA_RUNNING.store(true, Ordering::SeqCst);
+    let infrared = options.pixel_format == StreamPixelFormat::Infrared;
+    INFRARED_STREAM.store(infrared, Ordering::SeqCst);
+    let _state_guard = StreamStateGuard;
+    clear_last_capture();
+
+    // Open camera and stream, retrying while the device may still be appearing
+    let mut attempt = 1;
+    let mut camera = loop {
+        match open_with_fallback(&app, &options) {
+            Ok(cam) => break cam,
+            Err(message) if attempt < options.open_attempts => {
+                let _ = app.emit(
+                    "camera-init-retry",
+                    CameraInitRetry {
+                        attempt,
+                        max_attempts: options.open_attempts,
+                        error: message,
+                    },
+                );
+                if !wait_unless_stopped(options.retry_delay) {
+                    return;
+                }
+                attempt += 1;
+            }
+            Err(message) => {
+                let _ = app.emit("camera-error", CameraError { message });
+                return;
+            }
+        }
+    };
+
+    // Publish what the camera actually gave us
+    {
+        let mut guard = CAMERA_FORMAT.write();
+        *guard = Some(format_info(&camera));
+    }
+
+    {
+        let mut guard = PREVIEW_COUNTERS.write();
+        *guard = PreviewCounters {
+            started: Some(Instant::now()),
+            ..Default::default()
+        };
+    }
+
+    // Main capture loop
+    loop {
+        // Check stop signal
+        if STOP_SIGNAL.load(Ordering::SeqCst) {
+            break;
+        }
+
+        // Apply a white balance change from set_white_balance
+        if let Some((setting, reply)) = WHITE_BALANCE_REQUEST.lock().take() {
+            let _ = reply.send(apply_white_balance(&mut camera, setting));
+        }
+
+        // Bracket the exposure for capture_hdr
+        if let Some(reply) = HDR_REQUEST.lock().take() {
+            let _ = reply.send(capture_exposure_bracket(&mut camera));
+        }
+
+        // Report control ranges for get_camera_control_ranges
+        if let Some(reply) = CONTROL_RANGES_REQUEST.lock().take() {
+            let _ = reply.send(read_control_ranges(&camera));
+        }
+
+        // Time frame captures for measure_capture_latency
+        if let Some((samples, reply)) = LATENCY_REQUEST.lock().take() {
+            let _ = reply.send(time_frames(&mut camera, samples));
+        }
+
+        // Stop or restart the device stream when a pause is requested or lifted;
+        // the camera itself stays open so resuming is quick
+        let pause_requested = PAUSE_REQUESTED.load(Ordering::SeqCst);
+        if pause_requested != CAMERA_PAUSED.load(Ordering::SeqCst) {
+            if pause_requested {
+                if let Err(e) = camera.stop_stream() {
+                    eprintln!("Failed to pause camera stream: {}", e);
+                }
+                clear_frame_buffers();
+            } else if let Err(e) = camera.open_stream() {
+                let _ = app.emit(
+                    "camera-error",
+                    CameraError {
+                        message: format!("Failed to resume camera stream: {}", e),
+                    },
+                );
+                break;
+            }
+            CAMERA_PAUSED.store(pause_requested, Ordering::SeqCst);
+        }
+        if pause_requested {
+            thread::sleep(Duration::from_millis(50));
+            continue;
+        }
+
+        let frame_start = std::time::Instant::now();
+        let mut encode_time = None;
+        // Taken once per frame so a set_preview_config call never applies halfway
+        let preview_config = *PREVIEW_CONFIG.read();
+
+        // Capture frame
+        match camera.frame() {
+            Ok(frame) => {
+                let encode_start = Instant::now();
+                // Pass YUYV through untouched; fall back to RGB if the camera didn't give us YUYV
+                if options.pixel_format == StreamPixelFormat::Yuyv
+                    && frame.source_frame_format() == FrameFormat::YUYV
+                {
+                    emit_yuyv_frame(&app, &frame);
+                    encode_time = Some(encode_start.elapsed());
+                } else if infrared {
+                    let preview_width = options.preview_width;
+                    let emitted = frame
+                        .decode_image::<LumaFormat>()
+                        .map_err(|e| format!("Failed to decode infrared frame: {}", e))
+                        .and_then(|gray| {
+                            emit_infrared_frame(&app, gray, preview_width, preview_config)
+                        });
+                    match emitted {
+                        Ok(()) => encode_time = Some(encode_start.elapsed()),
+                        Err(e) => eprintln!("{}", e),
+                    }
+                } else if let Ok(decoded) = frame.decode_image::<RgbFormat>() {
+                    // Create image buffer
+                    if let Some(img) = ImageBuffer::<Rgb<u8>, Vec<u8>>::from_raw(
+                        decoded.width(),
+                        decoded.height(),
+                        decoded.into_raw(),
+                    ) {
+                        // Downscale the preview only; captures keep the full-resolution pixels
+                        let preview_width = preview_target_width(
+                            img.width(),
+                            options.preview_width,
+                            preview_config,
+                        );
+                        let preview = (preview_width < img.width())
+                            .then(|| scale_to_width(&img, preview_width));
+                        let preview_img = preview.as_ref().unwrap_or(&img);
+
+                        // Encode to JPEG once - for streaming, and for capture unless
+                        // downscaled or at a different quality
+                        let mut jpeg_buffer = Cursor::new(Vec::new());
+                        if image::codecs::jpeg::JpegEncoder::new_with_quality(
+                            &mut jpeg_buffer,
+                            preview_config.jpeg_quality,
+                        )
+                        .encode_image(preview_img)
+                        .is_ok()
+                        {
+                            let jpeg_bytes = jpeg_buffer.into_inner();
+                            let (width, height) = preview_img.dimensions();
+
+                            // Store JPEG and RGB pixels for capture (a downscaled or
+                            // re-tuned preview JPEG is never saved; captures encode from RGB)
+                            {
+                                let reusable = preview.is_none()
+                                    && preview_config.jpeg_quality == JPEG_QUALITY;
+                                let mut guard = LATEST_FRAME.write();
+                                *guard = reusable.then(|| jpeg_bytes.clone());
+                            }
+                            {
+                                let mut guard = LATEST_RGB_FRAME.write();
+                                *guard = Some(img);
+                            }
+
+                            // Convert to base64 and emit
+                            let base64_data = STANDARD.encode(&jpeg_bytes);
+                            let _ = app.emit(
+                                "camera-frame",
+                                CameraFrame {
+                                    data: format!("data:image/jpeg;base64,{}", base64_data),
+                                    width,
+                                    height,
+                                    format: "jpeg".to_string(),
+                                    stream: "color".to_string(),
+                                },
+                            );
+                            encode_time = Some(encode_start.elapsed());
+                        }
+                    }
+                }
+            }
+            Err(e) => {
+                eprintln!("Camera frame error: {}", e);
+            }
+        }
+        record_preview_frame(encode_time);
+
+        // Maintain the preview frame rate
+        let frame_interval = Duration::from_millis(1000 / u64::from(preview_config.fps));
+        let elapsed = frame_start.elapsed();
+        if elapsed < frame_interval {
+            thread::sleep(frame_interval - elapsed);
+        }
+    }
+
+    // Cleanup (flags and frame buffers are reset by the state guard)
+    let _ = camera.stop_stream();
 
     println!("Camera stream stopped");
 }
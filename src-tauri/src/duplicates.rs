@@ -0,0 +1,135 @@
+//! Near-duplicate detection for gallery images
+//!
+//! Images are compared by a 64-bit DCT perceptual hash; the Hamming distance
+//! between two hashes is small when the pictures look alike, even after
+//! re-encoding or slight exposure changes. Hashes are cached in memory by
+//! filename and modification time, so rescans only hash new or changed files.
+
+use crate::error::AppError;
+use crate::gallery::{camera_dir, is_in_camera_dir, list_gallery_images, GalleryImage};
+use image_hasher::{HashAlg, HasherConfig, ImageHash};
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+// Hash size in bits per side; 8x8 gives 64-bit hashes
+const HASH_SIDE: u32 = 8;
+
+lazy_static::lazy_static! {
+    // Hashes keyed by filename, valid while the modification time matches
+    static ref HASH_CACHE: Mutex<HashMap<String, (u64, ImageHash)>> = Mutex::new(HashMap::new());
+}
+
+/// Perceptual hash of a gallery image, base64-encoded
+#[tauri::command]
+pub async fn image_phash(path: String) -> Result<String, AppError> {
+    let camera_dir = camera_dir()?;
+    let target = Path::new(&path).to_path_buf();
+    if !is_in_camera_dir(&camera_dir, &target) || !target.is_file() {
+        return Err(AppError::InvalidArgument(format!(
+            "Not a gallery image: {}",
+            path
+        )));
+    }
+
+    let hash = tauri::async_runtime::spawn_blocking(move || cached_hash(&target))
+        .await
+        .map_err(|e| format!("Failed to hash image: {}", e))??;
+    Ok(hash.to_base64())
+}
+
+/// Group gallery images whose hashes differ by at most `threshold` bits (0-64)
+/// Only groups of two or more are returned, most recent image first in each.
+/// Images that can't be decoded are skipped.
+#[tauri::command]
+pub async fn find_duplicate_images(threshold: u32) -> Result<Vec<Vec<GalleryImage>>, AppError> {
+    if threshold > HASH_SIDE * HASH_SIDE {
+        return Err(AppError::InvalidArgument(format!(
+            "Threshold must be between 0 and {} bits",
+            HASH_SIDE * HASH_SIDE
+        )));
+    }
+
+    let images = list_gallery_images(None).await?;
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let hashed: Vec<(GalleryImage, ImageHash)> = images
+            .into_iter()
+            .filter_map(|image| match cached_hash(Path::new(&image.path)) {
+                Ok(hash) => Some((image, hash)),
+                Err(e) => {
+                    eprintln!("Skipping {} in duplicate scan: {}", image.filename, e);
+                    None
+                }
+            })
+            .collect();
+
+        // Link every close pair; groups are the connected sets
+        let mut group_of: Vec<usize> = (0..hashed.len()).collect();
+        for i in 0..hashed.len() {
+            for j in i + 1..hashed.len() {
+                if hashed[i].1.dist(&hashed[j].1) <= threshold {
+                    let (a, b) = (root(&mut group_of, i), root(&mut group_of, j));
+                    group_of[b] = a;
+                }
+            }
+        }
+
+        let mut groups: HashMap<usize, Vec<GalleryImage>> = HashMap::new();
+        for (i, (image, _)) in hashed.into_iter().enumerate() {
+            let group = root(&mut group_of, i);
+            groups.entry(group).or_default().push(image);
+        }
+
+        let mut groups: Vec<Vec<GalleryImage>> =
+            groups.into_values().filter(|g| g.len() > 1).collect();
+        for group in &mut groups {
+            group.sort_by_key(|image| std::cmp::Reverse(image.modified));
+        }
+        groups.sort_by_key(|group| std::cmp::Reverse(group[0].modified));
+        groups
+    })
+    .await
+    .map_err(|e| format!("Failed to find duplicates: {}", e).into())
+}
+
+/// Hash an image, reusing the cached hash while its modification time is unchanged
+fn cached_hash(path: &Path) -> Result<ImageHash, String> {
+    let filename = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .ok_or("Image has no file name")?;
+    let modified = fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+
+    if let Some((cached_modified, hash)) = HASH_CACHE.lock().get(&filename) {
+        if *cached_modified == modified {
+            return Ok(hash.clone());
+        }
+    }
+
+    let image = image::open(path).map_err(|e| format!("Failed to decode image: {}", e))?;
+    let hash = HasherConfig::new()
+        .hash_size(HASH_SIDE, HASH_SIDE)
+        .hash_alg(HashAlg::Mean)
+        .preproc_dct()
+        .to_hasher()
+        .hash_image(&image);
+
+    HASH_CACHE.lock().insert(filename, (modified, hash.clone()));
+    Ok(hash)
+}
+
+/// Follow group links to the representative index, flattening the path on the way
+fn root(group_of: &mut [usize], mut i: usize) -> usize {
+    while group_of[i] != i {
+        group_of[i] = group_of[group_of[i]];
+        i = group_of[i];
+    }
+    i
+}
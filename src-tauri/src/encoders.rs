@@ -0,0 +1,200 @@
+//! Compressed audio encoders for recordings
+//!
+//! MP3 (LAME), Ogg Vorbis (libvorbis) and FLAC. Like the WAV code, samples come in
+//! as interleaved f32 in -1.0..1.0. Each encoder calls `progress` with the fraction
+//! of the audio encoded so far.
+
+use flacenc::bitsink::ByteSink;
+use flacenc::component::BitRepr;
+use flacenc::error::{SourceError, Verify};
+use flacenc::source::{Fill, Source};
+use mp3lame_encoder::{
+    max_required_buffer_size, Bitrate, Builder, DualPcm, FlushNoGap, MonoPcm, Quality,
+};
+use std::num::{NonZeroU32, NonZeroU8};
+use vorbis_rs::VorbisEncoderBuilder;
+
+// Frames handed to the MP3 and Vorbis encoders at a time
+const ENCODE_CHUNK_FRAMES: usize = 4096;
+
+/// Encode as 128 kbps constant-bitrate MP3 (mono or stereo only)
+pub fn encode_mp3(
+    samples: &[f32],
+    sample_rate: u32,
+    channels: u16,
+    mut progress: impl FnMut(f32),
+) -> Result<Vec<u8>, String> {
+    if !(1..=2).contains(&channels) {
+        return Err("MP3 supports mono or stereo audio only".to_string());
+    }
+
+    let mut builder = Builder::new().ok_or("Failed to create MP3 encoder")?;
+    builder
+        .set_num_channels(channels as u8)
+        .and_then(|_| builder.set_sample_rate(sample_rate))
+        .and_then(|_| builder.set_brate(Bitrate::Kbps128))
+        .and_then(|_| builder.set_quality(Quality::Good))
+        .map_err(|e| format!("Failed to set up MP3 encoder: {}", e))?;
+    let mut encoder = builder
+        .build()
+        .map_err(|e| format!("Failed to set up MP3 encoder: {}", e))?;
+
+    let chunk_len = ENCODE_CHUNK_FRAMES * channels as usize;
+    let mut mp3 = Vec::new();
+    let (mut left, mut right) = (Vec::new(), Vec::new());
+    for (i, chunk) in samples.chunks(chunk_len).enumerate() {
+        mp3.reserve(max_required_buffer_size(chunk.len() / channels as usize));
+        let encoded = if channels == 1 {
+            encoder.encode_to_vec(MonoPcm(chunk), &mut mp3)
+        } else {
+            left.clear();
+            right.clear();
+            for frame in chunk.chunks_exact(2) {
+                left.push(frame[0]);
+                right.push(frame[1]);
+            }
+            encoder.encode_to_vec(
+                DualPcm {
+                    left: &left,
+                    right: &right,
+                },
+                &mut mp3,
+            )
+        };
+        encoded.map_err(|e| format!("Failed to encode MP3: {}", e))?;
+        progress(fraction((i + 1) * chunk_len, samples.len()));
+    }
+
+    mp3.reserve(max_required_buffer_size(0));
+    encoder
+        .flush_to_vec::<FlushNoGap>(&mut mp3)
+        .map_err(|e| format!("Failed to encode MP3: {}", e))?;
+    Ok(mp3)
+}
+
+/// Encode as Ogg Vorbis at the encoder's default quality
+pub fn encode_ogg(
+    samples: &[f32],
+    sample_rate: u32,
+    channels: u16,
+    mut progress: impl FnMut(f32),
+) -> Result<Vec<u8>, String> {
+    let rate = NonZeroU32::new(sample_rate).ok_or("Invalid sample rate")?;
+    let channel_count = u8::try_from(channels)
+        .ok()
+        .and_then(NonZeroU8::new)
+        .ok_or("Invalid channel count")?;
+
+    let mut encoder = VorbisEncoderBuilder::new(rate, channel_count, Vec::new())
+        .and_then(|mut builder| builder.build())
+        .map_err(|e| format!("Failed to set up Ogg encoder: {}", e))?;
+
+    // Vorbis takes one buffer per channel
+    let chunk_len = ENCODE_CHUNK_FRAMES * channels as usize;
+    let mut planar = vec![Vec::with_capacity(ENCODE_CHUNK_FRAMES); channels as usize];
+    for (i, chunk) in samples.chunks(chunk_len).enumerate() {
+        for channel in &mut planar {
+            channel.clear();
+        }
+        for frame in chunk.chunks_exact(channels as usize) {
+            for (channel, &sample) in planar.iter_mut().zip(frame) {
+                channel.push(sample);
+            }
+        }
+        encoder
+            .encode_audio_block(&planar)
+            .map_err(|e| format!("Failed to encode Ogg: {}", e))?;
+        progress(fraction((i + 1) * chunk_len, samples.len()));
+    }
+
+    encoder
+        .finish()
+        .map_err(|e| format!("Failed to encode Ogg: {}", e))
+}
+
+/// Encode as lossless FLAC with `bits_per_sample` (16 or 24) resolution
+pub fn encode_flac(
+    samples: &[f32],
+    sample_rate: u32,
+    channels: u16,
+    bits_per_sample: usize,
+    progress: impl FnMut(f32),
+) -> Result<Vec<u8>, String> {
+    let scale = ((1i64 << (bits_per_sample - 1)) - 1) as f32;
+    let pcm: Vec<i32> = samples
+        .iter()
+        .map(|s| (s.clamp(-1.0, 1.0) * scale).round() as i32)
+        .collect();
+
+    let config = flacenc::config::Encoder::default()
+        .into_verified()
+        .map_err(|(_, e)| format!("Failed to set up FLAC encoder: {}", e))?;
+    let source = ProgressSource {
+        samples: &pcm,
+        channels: channels as usize,
+        bits_per_sample,
+        sample_rate: sample_rate as usize,
+        read_frames: 0,
+        progress,
+    };
+    let stream = flacenc::encode_with_fixed_block_size(&config, source, config.block_size)
+        .map_err(|e| format!("Failed to encode FLAC: {}", e))?;
+
+    let mut sink = ByteSink::new();
+    stream
+        .write(&mut sink)
+        .map_err(|e| format!("Failed to encode FLAC: {}", e))?;
+    Ok(sink.into_inner())
+}
+
+/// In-memory FLAC source that reports how far the encoder has read
+struct ProgressSource<'a, P> {
+    samples: &'a [i32], // interleaved
+    channels: usize,
+    bits_per_sample: usize,
+    sample_rate: usize,
+    read_frames: usize,
+    progress: P,
+}
+
+impl<P: FnMut(f32)> Source for ProgressSource<'_, P> {
+    fn channels(&self) -> usize {
+        self.channels
+    }
+
+    fn bits_per_sample(&self) -> usize {
+        self.bits_per_sample
+    }
+
+    fn sample_rate(&self) -> usize {
+        self.sample_rate
+    }
+
+    fn read_samples<F: Fill>(
+        &mut self,
+        block_size: usize,
+        dest: &mut F,
+    ) -> Result<usize, SourceError> {
+        let begin = (self.read_frames * self.channels).min(self.samples.len());
+        let end = ((self.read_frames + block_size) * self.channels).min(self.samples.len());
+        dest.fill_interleaved(&self.samples[begin..end])?;
+
+        let read = (end - begin) / self.channels;
+        self.read_frames += read;
+        (self.progress)(fraction(end, self.samples.len()));
+        Ok(read)
+    }
+
+    fn len_hint(&self) -> Option<usize> {
+        Some(self.samples.len() / self.channels)
+    }
+}
+
+/// Share of `total` that `done` covers, capped at 1.0
+fn fraction(done: usize, total: usize) -> f32 {
+    if total == 0 {
+        1.0
+    } else {
+        (done as f32 / total as f32).min(1.0)
+    }
+}
@@ -0,0 +1,97 @@
+//! Encryption of saved recordings at rest
+//!
+//! Files are AES-256-GCM encrypted and stored as `<name>.wav.enc`:
+//! an 8-byte magic, a random 12-byte nonce, then the ciphertext with its tag.
+//! The key lives in memory only and has to be set again after a restart.
+
+use crate::error::AppError;
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit, OsRng},
+    Aes256Gcm, Key, Nonce,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use parking_lot::RwLock;
+use std::path::Path;
+
+// Extension of encrypted recordings, after their own ".wav"
+pub(crate) const ENCRYPTED_EXTENSION: &str = "wav.enc";
+
+const MAGIC: &[u8; 8] = b"HBENC\x00\x00\x01";
+const NONCE_LEN: usize = 12;
+const KEY_LEN: usize = 32;
+
+lazy_static::lazy_static! {
+    static ref ENCRYPTION_KEY: RwLock<Option<[u8; KEY_LEN]>> = RwLock::new(None);
+}
+
+/// Set the key new recordings are encrypted with, as base64 of 32 random bytes
+/// Pass null to turn encryption off; files already encrypted stay encrypted.
+#[tauri::command]
+pub async fn set_encryption_key(key: Option<String>) -> Result<(), AppError> {
+    let key = match key {
+        Some(encoded) => {
+            let bytes = STANDARD
+                .decode(encoded.trim())
+                .map_err(|e| format!("Invalid encryption key: {}", e))?;
+            let key: [u8; KEY_LEN] = bytes.try_into().map_err(|bytes: Vec<u8>| {
+                format!(
+                    "Invalid encryption key: expected {} bytes, got {}",
+                    KEY_LEN,
+                    bytes.len()
+                )
+            })?;
+            Some(key)
+        }
+        None => None,
+    };
+
+    *ENCRYPTION_KEY.write() = key;
+    Ok(())
+}
+
+/// True when new recordings should be encrypted
+pub(crate) fn encryption_enabled() -> bool {
+    ENCRYPTION_KEY.read().is_some()
+}
+
+/// True for `<name>.wav.enc` paths
+pub(crate) fn is_encrypted_path(path: &Path) -> bool {
+    path.file_name()
+        .map(|n| n.to_string_lossy().to_lowercase())
+        .is_some_and(|name| name.ends_with(&format!(".{}", ENCRYPTED_EXTENSION)))
+}
+
+/// Encrypt `plain` with the configured key into the on-disk layout
+pub(crate) fn encrypt(plain: &[u8]) -> Result<Vec<u8>, String> {
+    let cipher = cipher()?;
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, plain)
+        .map_err(|e| format!("Failed to encrypt recording: {}", e))?;
+
+    let mut data = Vec::with_capacity(MAGIC.len() + NONCE_LEN + ciphertext.len());
+    data.extend_from_slice(MAGIC);
+    data.extend_from_slice(&nonce);
+    data.extend_from_slice(&ciphertext);
+    Ok(data)
+}
+
+/// Decrypt data written by `encrypt`
+pub(crate) fn decrypt(data: &[u8]) -> Result<Vec<u8>, String> {
+    let cipher = cipher()?;
+    let body = data
+        .strip_prefix(MAGIC.as_slice())
+        .filter(|body| body.len() >= NONCE_LEN)
+        .ok_or("Not an encrypted recording")?;
+    let (nonce, ciphertext) = body.split_at(NONCE_LEN);
+
+    // GCM can't tell a wrong key from tampering; both fail authentication
+    cipher
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| "Failed to decrypt recording: wrong key or corrupted file".to_string())
+}
+
+fn cipher() -> Result<Aes256Gcm, String> {
+    let key = ENCRYPTION_KEY.read().ok_or("No encryption key set")?;
+    Ok(Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key)))
+}
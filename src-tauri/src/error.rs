@@ -0,0 +1,86 @@
+//! Errors returned by commands
+//!
+//! Serialized as `{ "code": "device_not_found", "message": "No input device found" }`.
+//! `code` is stable across versions for the frontend to branch on; `message` is for
+//! people and may change. Internal helpers keep returning `String` errors, which
+//! convert to `Other` when propagated with `?`.
+
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+use std::fmt;
+
+#[derive(Debug, Clone)]
+pub enum AppError {
+    CameraNotInitialized(String), // stream not running, paused or without frames yet
+    DeviceNotFound(String),       // no camera, microphone, speaker or display
+    IoError(String),
+    DecodeError(String), // unreadable image or audio data
+    PathOutsideAllowedDir(String),
+    InvalidArgument(String),
+    Unsupported(String), // not available on this platform
+    Other(String),
+}
+
+impl AppError {
+    /// Stable identifier for the kind of error
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::CameraNotInitialized(_) => "camera_not_initialized",
+            AppError::DeviceNotFound(_) => "device_not_found",
+            AppError::IoError(_) => "io_error",
+            AppError::DecodeError(_) => "decode_error",
+            AppError::PathOutsideAllowedDir(_) => "path_outside_allowed_dir",
+            AppError::InvalidArgument(_) => "invalid_argument",
+            AppError::Unsupported(_) => "unsupported",
+            AppError::Other(_) => "other",
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            AppError::CameraNotInitialized(message)
+            | AppError::DeviceNotFound(message)
+            | AppError::IoError(message)
+            | AppError::DecodeError(message)
+            | AppError::PathOutsideAllowedDir(message)
+            | AppError::InvalidArgument(message)
+            | AppError::Unsupported(message)
+            | AppError::Other(message) => message,
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl Serialize for AppError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("AppError", 2)?;
+        state.serialize_field("code", self.code())?;
+        state.serialize_field("message", self.message())?;
+        state.end()
+    }
+}
+
+impl From<String> for AppError {
+    fn from(message: String) -> Self {
+        AppError::Other(message)
+    }
+}
+
+impl From<&str> for AppError {
+    fn from(message: &str) -> Self {
+        AppError::Other(message.to_string())
+    }
+}
+
+// Lets helpers that still return String errors call commands with `?`
+impl From<AppError> for String {
+    fn from(error: AppError) -> Self {
+        error.message().to_string()
+    }
+}
@@ -0,0 +1,149 @@
+//! Minimal EXIF writer for saved photos
+//!
+//! Builds a little-endian TIFF block with ImageDescription, DateTime and
+//! Artist in IFD0 plus DateTimeOriginal in the Exif sub-IFD, and inserts it
+//! as an APP1 segment right after the JPEG's SOI/JFIF header.
+
+use chrono::{DateTime, Local};
+
+const TAG_IMAGE_DESCRIPTION: u16 = 0x010E;
+const TAG_DATE_TIME: u16 = 0x0132;
+const TAG_ARTIST: u16 = 0x013B;
+const TAG_EXIF_IFD_POINTER: u16 = 0x8769;
+const TAG_DATE_TIME_ORIGINAL: u16 = 0x9003;
+
+const TYPE_ASCII: u16 = 2;
+const TYPE_LONG: u16 = 4;
+
+// Offset of IFD0 from the start of the TIFF header
+const IFD0_OFFSET: u32 = 8;
+
+/// Metadata written into a photo
+pub struct PhotoMetadata {
+    pub captured_at: DateTime<Local>,
+    pub description: Option<String>,
+    pub artist: Option<String>,
+}
+
+#[derive(Clone)]
+enum IfdValue {
+    Ascii(Vec<u8>), // NUL-terminated
+    Long(u32),
+}
+
+/// Return a copy of `jpeg` with an EXIF APP1 segment carrying `metadata`
+pub fn add_exif_to_jpeg(jpeg: &[u8], metadata: &PhotoMetadata) -> Result<Vec<u8>, String> {
+    if jpeg.len() < 4 || jpeg[0..2] != [0xFF, 0xD8] {
+        return Err("Not a JPEG image".to_string());
+    }
+
+    // Keep SOI and a leading JFIF APP0 segment in front of the EXIF block
+    let mut insert_at = 2;
+    if jpeg[2..4] == [0xFF, 0xE0] && jpeg.len() >= 6 {
+        insert_at = (4 + u16::from_be_bytes([jpeg[4], jpeg[5]]) as usize).min(jpeg.len());
+    }
+
+    let tiff = build_tiff(metadata);
+    let segment_len = 2 + 6 + tiff.len();
+    if segment_len > u16::MAX as usize {
+        return Err("EXIF metadata is too large".to_string());
+    }
+
+    let mut out = Vec::with_capacity(jpeg.len() + segment_len + 2);
+    out.extend_from_slice(&jpeg[..insert_at]);
+    out.extend_from_slice(&[0xFF, 0xE1]);
+    out.extend_from_slice(&(segment_len as u16).to_be_bytes());
+    out.extend_from_slice(b"Exif\0\0");
+    out.extend_from_slice(&tiff);
+    out.extend_from_slice(&jpeg[insert_at..]);
+    Ok(out)
+}
+
+/// Build the TIFF header, IFD0 and Exif sub-IFD
+fn build_tiff(metadata: &PhotoMetadata) -> Vec<u8> {
+    let timestamp = ascii_value(&metadata.captured_at.format("%Y:%m:%d %H:%M:%S").to_string());
+
+    let ifd0_entries = |exif_offset: u32| {
+        let mut entries = Vec::new();
+        if let Some(description) = &metadata.description {
+            entries.push((TAG_IMAGE_DESCRIPTION, ascii_value(description)));
+        }
+        entries.push((TAG_DATE_TIME, timestamp.clone()));
+        if let Some(artist) = &metadata.artist {
+            entries.push((TAG_ARTIST, ascii_value(artist)));
+        }
+        entries.push((TAG_EXIF_IFD_POINTER, IfdValue::Long(exif_offset)));
+        entries
+    };
+
+    // IFD0's size doesn't depend on the pointer value, so encode once to place the sub-IFD
+    let ifd0_len = encode_ifd(&ifd0_entries(0), IFD0_OFFSET).len() as u32;
+    let exif_offset = IFD0_OFFSET + ifd0_len;
+
+    let mut tiff = Vec::new();
+    tiff.extend_from_slice(b"II");
+    tiff.extend_from_slice(&42u16.to_le_bytes());
+    tiff.extend_from_slice(&IFD0_OFFSET.to_le_bytes());
+    tiff.extend(encode_ifd(&ifd0_entries(exif_offset), IFD0_OFFSET));
+    tiff.extend(encode_ifd(
+        &[(TAG_DATE_TIME_ORIGINAL, timestamp)],
+        exif_offset,
+    ));
+    tiff
+}
+
+/// Encode one IFD (entries, next-IFD link, then values too big to inline) placed at `offset`
+fn encode_ifd(entries: &[(u16, IfdValue)], offset: u32) -> Vec<u8> {
+    let mut ifd = Vec::new();
+    let mut data = Vec::new();
+    let data_start = offset + 2 + 12 * entries.len() as u32 + 4;
+
+    ifd.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+    for (tag, value) in entries {
+        ifd.extend_from_slice(&tag.to_le_bytes());
+        match value {
+            IfdValue::Long(v) => {
+                ifd.extend_from_slice(&TYPE_LONG.to_le_bytes());
+                ifd.extend_from_slice(&1u32.to_le_bytes());
+                ifd.extend_from_slice(&v.to_le_bytes());
+            }
+            IfdValue::Ascii(bytes) => {
+                ifd.extend_from_slice(&TYPE_ASCII.to_le_bytes());
+                ifd.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+                if bytes.len() <= 4 {
+                    let mut inline = [0u8; 4];
+                    inline[..bytes.len()].copy_from_slice(bytes);
+                    ifd.extend_from_slice(&inline);
+                } else {
+                    ifd.extend_from_slice(&(data_start + data.len() as u32).to_le_bytes());
+                    data.extend_from_slice(bytes);
+                    // Values start on word boundaries
+                    if data.len() % 2 == 1 {
+                        data.push(0);
+                    }
+                }
+            }
+        }
+    }
+    // No next IFD
+    ifd.extend_from_slice(&0u32.to_le_bytes());
+
+    ifd.extend(data);
+    ifd
+}
+
+/// EXIF ASCII value: 7-bit characters only, NUL-terminated
+fn ascii_value(text: &str) -> IfdValue {
+    let mut bytes: Vec<u8> = text
+        .chars()
+        .map(|c| {
+            if c.is_ascii() && !c.is_ascii_control() {
+                c as u8
+            } else {
+                b'?'
+            }
+        })
+        .collect();
+    bytes.push(0);
+    IfdValue::Ascii(bytes)
+}
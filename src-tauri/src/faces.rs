@@ -0,0 +1,155 @@
+//! Face detection on the live camera frame
+//!
+//! Uses rustface (a port of the SeetaFace frontal detector). The model file
+//! `seeta_fd_frontal_v1.0.bin` is bundled as a Tauri resource under
+//! `resources/` (see `bundle.resources` in tauri.conf.json) and resolved via
+//! the app's resource directory, so the same lookup works in `tauri dev`
+//! and in packaged builds.
+
+use crate::camera::{aspect_crop_size, encode_jpeg, latest_frame_rgb, stream_kind, CameraFrame};
+use crate::error::AppError;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use image::{DynamicImage, RgbImage};
+use parking_lot::Mutex;
+use rustface::{ImageData, Model};
+use tauri::{path::BaseDirectory, AppHandle, Manager};
+
+const FACE_MODEL_RESOURCE: &str = "resources/seeta_fd_frontal_v1.0.bin";
+
+// Detector tuning (defaults recommended by SeetaFace)
+const MIN_FACE_SIZE: u32 = 40;
+const SCORE_THRESHOLD: f64 = 2.0;
+const PYRAMID_SCALE_FACTOR: f32 = 0.8;
+const SLIDE_WINDOW_STEP: u32 = 4;
+
+lazy_static::lazy_static! {
+    // Parsed model, loaded on first use
+    static ref FACE_MODEL: Mutex<Option<Model>> = Mutex::new(None);
+}
+
+/// Face bounding box in frame pixel coordinates
+#[derive(Clone, serde::Serialize)]
+pub struct FaceRect {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub confidence: f64,
+}
+
+/// Frame with a suggested crop around its main subject (payload of suggest_crop)
+#[derive(Clone, serde::Serialize)]
+pub struct CropSuggestion {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    pub subject: Option<FaceRect>, // face the crop is centered on; None for a centered crop
+    pub frame: CameraFrame,        // the full frame the crop applies to
+}
+
+/// Load the face model from the bundled resources (cached after first load)
+fn load_face_model(app: &AppHandle) -> Result<Model, String> {
+    let mut guard = FACE_MODEL.lock();
+    if let Some(model) = guard.as_ref() {
+        return Ok(model.clone());
+    }
+
+    let model_path = app
+        .path()
+        .resolve(FACE_MODEL_RESOURCE, BaseDirectory::Resource)
+        .map_err(|e| format!("Failed to resolve face model path: {}", e))?;
+
+    let model = rustface::load_model(&model_path.to_string_lossy())
+        .map_err(|e| format!("Failed to load face model {:?}: {}", model_path, e))?;
+
+    *guard = Some(model.clone());
+    Ok(model)
+}
+
+/// Detect faces in the current camera frame
+#[tauri::command]
+pub async fn detect_faces(app: AppHandle) -> Result<Vec<FaceRect>, AppError> {
+    let frame = latest_frame_rgb()?;
+    Ok(find_faces(&app, frame)?)
+}
+
+/// Capture a frame and suggest the largest `target_ratio` crop centered on the main face
+/// The largest face counts as the subject; without one the crop is centered in the frame.
+/// Nothing is saved: the frame is returned so the crop can be previewed and confirmed.
+#[tauri::command]
+pub async fn suggest_crop(
+    app: AppHandle,
+    target_ratio: (u32, u32),
+) -> Result<CropSuggestion, AppError> {
+    let (ratio_w, ratio_h) = target_ratio;
+    if ratio_w == 0 || ratio_h == 0 {
+        return Err(AppError::InvalidArgument(
+            "Aspect ratio must be non-zero".to_string(),
+        ));
+    }
+
+    let frame = latest_frame_rgb()?;
+    let (frame_w, frame_h) = frame.dimensions();
+    let subject = find_faces(&app, frame.clone())?
+        .into_iter()
+        .max_by_key(|face| face.width as u64 * face.height as u64);
+
+    // Center on the subject, sliding the crop back inside the frame near the edges
+    let (width, height) = aspect_crop_size(frame_w, frame_h, ratio_w, ratio_h);
+    let (center_x, center_y) = match &subject {
+        Some(face) => (
+            face.x as i64 + face.width as i64 / 2,
+            face.y as i64 + face.height as i64 / 2,
+        ),
+        None => (frame_w as i64 / 2, frame_h as i64 / 2),
+    };
+    let x = (center_x - width as i64 / 2).clamp(0, (frame_w - width) as i64) as u32;
+    let y = (center_y - height as i64 / 2).clamp(0, (frame_h - height) as i64) as u32;
+
+    let jpeg = encode_jpeg(&frame)?;
+    Ok(CropSuggestion {
+        x,
+        y,
+        width,
+        height,
+        subject,
+        frame: CameraFrame {
+            data: format!("data:image/jpeg;base64,{}", STANDARD.encode(&jpeg)),
+            width: frame_w,
+            height: frame_h,
+            format: "jpeg".to_string(),
+            stream: stream_kind(),
+        },
+    })
+}
+
+/// Run the face detector over a frame
+fn find_faces(app: &AppHandle, frame: RgbImage) -> Result<Vec<FaceRect>, String> {
+    let gray = DynamicImage::ImageRgb8(frame).to_luma8();
+
+    let mut detector = rustface::create_detector_with_model(load_face_model(app)?);
+    detector.set_min_face_size(MIN_FACE_SIZE);
+    detector.set_score_thresh(SCORE_THRESHOLD);
+    detector.set_pyramid_scale_factor(PYRAMID_SCALE_FACTOR);
+    detector.set_slide_window_step(SLIDE_WINDOW_STEP, SLIDE_WINDOW_STEP);
+
+    let image = ImageData::new(gray.as_raw(), gray.width(), gray.height());
+
+    let faces = detector
+        .detect(&image)
+        .iter()
+        .map(|face| {
+            let bbox = face.bbox();
+            FaceRect {
+                x: bbox.x(),
+                y: bbox.y(),
+                width: bbox.width(),
+                height: bbox.height(),
+                confidence: face.score(),
+            }
+        })
+        .collect();
+
+    Ok(faces)
+}
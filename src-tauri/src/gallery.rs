@@ -1,36 +1,82 @@
+use crate::camera::JPEG_QUALITY;
+use crate::error::AppError;
+use crate::media::{pictures_base_dir, unique_path};
 use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::Local;
+use image::{metadata::Orientation, DynamicImage, ImageDecoder, ImageFormat, ImageReader};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::HashMap;
 use std::fs;
+use std::io::{Cursor, Read, Seek, SeekFrom};
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
 
 const CAMERA_DIR: &str = "honeybee-camera";
 
+// Sidecar in the camera directory mapping filename -> favorite
+const FAVORITES_FILE: &str = ".favorites.json";
+// Sidecar in the camera directory mapping filename -> keyword tags (lowercase, sorted)
+const TAGS_FILE: &str = ".tags.json";
+
+// Quiet period before a burst of file events is reported as one change
+const GALLERY_DEBOUNCE: Duration = Duration::from_millis(300);
+
+static GALLERY_WATCHING: AtomicBool = AtomicBool::new(false);
+static STOP_GALLERY_WATCH: AtomicBool = AtomicBool::new(false);
+
+// Largest slice read_gallery_image_chunk returns in one call (4 MB)
+const MAX_CHUNK_LEN: u64 = 4 * 1024 * 1024;
+
+// Image file extensions shown in the gallery
+const IMAGE_EXTENSIONS: [&str; 3] = ["jpg", "jpeg", "png"];
+
 #[derive(Clone, serde::Serialize)]
 pub struct GalleryImage {
     pub filename: String,
     pub path: String,
     pub size: u64,
-    pub modified: u64, // unix timestamp
+    pub modified: u64,     // unix timestamp
+    pub extension: String, // lowercase, e.g. "jpg"
+    pub favorite: bool,
+    pub tags: Vec<String>,
 }
 
 /// List all images in ~/Pictures/honeybee-camera/
+/// `extensions` (e.g. ["png"]) limits the list to those file types
 #[tauri::command]
-pub async fn list_gallery_images() -> Result<Vec<GalleryImage>, String> {
-    let pictures_dir = dirs::picture_dir().ok_or("Failed to get Pictures directory")?;
-    let camera_dir = pictures_dir.join(CAMERA_DIR);
+pub async fn list_gallery_images(
+    extensions: Option<Vec<String>>,
+) -> Result<Vec<GalleryImage>, AppError> {
+    let camera_dir = camera_dir()?;
+    let extensions: Option<Vec<String>> = extensions.map(|exts| {
+        exts.iter()
+            .map(|ext| ext.trim_start_matches('.').to_lowercase())
+            .collect()
+    });
 
     if !camera_dir.exists() {
         return Ok(Vec::new());
     }
 
     let mut images: Vec<GalleryImage> = Vec::new();
+    let favorites = load_favorites(&camera_dir);
+    let mut tags = load_tags(&camera_dir);
 
     let entries = fs::read_dir(&camera_dir)
-        .map_err(|e| format!("Failed to read directory: {}", e))?;
+        .map_err(|e| AppError::IoError(format!("Failed to read directory: {}", e)))?;
 
     for entry in entries.flatten() {
         let path = entry.path();
         if let Some(ext) = path.extension() {
             let ext_lower = ext.to_string_lossy().to_lowercase();
-            if ext_lower == "jpg" || ext_lower == "jpeg" || ext_lower == "png" {
+            let wanted = extensions
+                .as_ref()
+                .is_none_or(|exts| exts.contains(&ext_lower));
+            if IMAGE_EXTENSIONS.contains(&ext_lower.as_str()) && wanted {
                 if let Ok(metadata) = entry.metadata() {
                     let modified = metadata
                         .modified()
@@ -39,11 +85,15 @@ pub async fn list_gallery_images() -> Result<Vec<GalleryImage>, String> {
                         .map(|d| d.as_secs())
                         .unwrap_or(0);
 
+                    let filename = entry.file_name().to_string_lossy().to_string();
                     images.push(GalleryImage {
-                        filename: entry.file_name().to_string_lossy().to_string(),
+                        favorite: favorites.get(&filename).copied().unwrap_or(false),
+                        tags: tags.remove(&filename).unwrap_or_default(),
+                        filename,
                         path: path.to_string_lossy().to_string(),
                         size: metadata.len(),
                         modified,
+                        extension: ext_lower,
                     });
                 }
             }
@@ -51,31 +101,440 @@ pub async fn list_gallery_images() -> Result<Vec<GalleryImage>, String> {
     }
 
     // Sort newest first
-    images.sort_by(|a, b| b.modified.cmp(&a.modified));
+    images.sort_by_key(|img| std::cmp::Reverse(img.modified));
 
     Ok(images)
 }
 
 /// Read an image as base64 data URL
+/// JPEGs with an EXIF orientation tag are rotated upright first
 #[tauri::command]
-pub async fn read_gallery_image(path: String) -> Result<String, String> {
-    let data = fs::read(&path).map_err(|e| format!("Failed to read image: {}", e))?;
+pub async fn read_gallery_image(path: String) -> Result<String, AppError> {
+    let data =
+        fs::read(&path).map_err(|e| AppError::IoError(format!("Failed to read image: {}", e)))?;
+    let data = apply_exif_orientation(&data)?.unwrap_or(data);
     let base64_data = STANDARD.encode(&data);
     Ok(format!("data:image/jpeg;base64,{}", base64_data))
 }
 
+/// Read `length` bytes of a gallery image from `offset`, base64-encoded
+/// The raw file bytes are returned (no EXIF rotation), so large images can be loaded
+/// progressively against `size` from GalleryImage. The last chunk may be shorter.
+#[tauri::command]
+pub async fn read_gallery_image_chunk(
+    path: String,
+    offset: u64,
+    length: u64,
+) -> Result<String, AppError> {
+    let camera_dir = camera_dir()?;
+    let target = Path::new(&path);
+    if !is_in_camera_dir(&camera_dir, target) || !target.is_file() {
+        return Err(AppError::InvalidArgument(format!(
+            "Not a gallery image: {}",
+            path
+        )));
+    }
+    if length == 0 || length > MAX_CHUNK_LEN {
+        return Err(AppError::InvalidArgument(format!(
+            "Chunk length must be between 1 and {} bytes",
+            MAX_CHUNK_LEN
+        )));
+    }
+
+    let mut file = fs::File::open(target)
+        .map_err(|e| AppError::IoError(format!("Failed to read image: {}", e)))?;
+    let size = file
+        .metadata()
+        .map_err(|e| AppError::IoError(format!("Failed to read image: {}", e)))?
+        .len();
+    if offset >= size {
+        return Err(AppError::InvalidArgument(format!(
+            "Offset {} is past the end of the image ({} bytes)",
+            offset, size
+        )));
+    }
+
+    let mut chunk = Vec::with_capacity(length.min(size - offset) as usize);
+    file.seek(SeekFrom::Start(offset))
+        .and_then(|_| file.take(length).read_to_end(&mut chunk))
+        .map_err(|e| AppError::IoError(format!("Failed to read image: {}", e)))?;
+    Ok(STANDARD.encode(&chunk))
+}
+
+/// Bake the EXIF orientation into the pixels of a JPEG
+/// Returns None when there is no orientation to apply (e.g. our own captures)
+fn apply_exif_orientation(data: &[u8]) -> Result<Option<Vec<u8>>, String> {
+    let reader = ImageReader::new(Cursor::new(data))
+        .with_guessed_format()
+        .map_err(|e| format!("Failed to read image: {}", e))?;
+
+    if reader.format() != Some(ImageFormat::Jpeg) {
+        return Ok(None);
+    }
+
+    let mut decoder = reader
+        .into_decoder()
+        .map_err(|e| format!("Failed to decode image: {}", e))?;
+    let orientation = decoder.orientation().unwrap_or(Orientation::NoTransforms);
+
+    if orientation == Orientation::NoTransforms {
+        return Ok(None);
+    }
+
+    let mut img = DynamicImage::from_decoder(decoder)
+        .map_err(|e| format!("Failed to decode image: {}", e))?;
+    img.apply_orientation(orientation);
+
+    let mut jpeg_buffer = Cursor::new(Vec::new());
+    image::codecs::jpeg::JpegEncoder::new_with_quality(&mut jpeg_buffer, JPEG_QUALITY)
+        .encode_image(&img.to_rgb8())
+        .map_err(|e| format!("Failed to encode image: {}", e))?;
+
+    Ok(Some(jpeg_buffer.into_inner()))
+}
+
 /// Delete an image
 #[tauri::command]
-pub async fn delete_gallery_image(path: String) -> Result<bool, String> {
+pub async fn delete_gallery_image(path: String) -> Result<bool, AppError> {
     // Safety: only allow deleting from the camera directory
-    let pictures_dir = dirs::picture_dir().ok_or("Failed to get Pictures directory")?;
-    let camera_dir = pictures_dir.join(CAMERA_DIR);
+    let camera_dir = camera_dir()?;
     let target = std::path::Path::new(&path);
 
-    if !target.starts_with(&camera_dir) {
-        return Err("Cannot delete files outside camera directory".to_string());
+    if !is_in_camera_dir(&camera_dir, target) {
+        return Err(AppError::PathOutsideAllowedDir(
+            "Cannot delete files outside camera directory".to_string(),
+        ));
+    }
+
+    fs::remove_file(&path)
+        .map_err(|e| AppError::IoError(format!("Failed to delete image: {}", e)))?;
+
+    // Drop its favorite flag and tags so a later file with the same name doesn't inherit
+    // them; the image is already gone, so a failed sidecar update is only logged
+    if let Some(filename) = target.file_name() {
+        let filename = filename.to_string_lossy();
+        let mut favorites = load_favorites(&camera_dir);
+        if favorites.remove(&*filename).is_some() {
+            if let Err(e) = save_favorites(&camera_dir, &favorites) {
+                eprintln!("Failed to clean up favorite for {}: {}", path, e);
+            }
+        }
+        let mut tags = load_tags(&camera_dir);
+        if tags.remove(&*filename).is_some() {
+            if let Err(e) = save_tags(&camera_dir, &tags) {
+                eprintln!("Failed to clean up tags for {}: {}", path, e);
+            }
+        }
     }
 
-    fs::remove_file(&path).map_err(|e| format!("Failed to delete image: {}", e))?;
     Ok(true)
 }
+
+/// Mark or unmark an image as a favorite
+#[tauri::command]
+pub async fn set_gallery_favorite(path: String, favorite: bool) -> Result<(), AppError> {
+    let camera_dir = camera_dir()?;
+    let filename = gallery_filename(&camera_dir, &path)?;
+
+    let mut favorites = load_favorites(&camera_dir);
+    if favorite {
+        favorites.insert(filename, true);
+    } else {
+        favorites.remove(&filename);
+    }
+    save_favorites(&camera_dir, &favorites)?;
+    Ok(())
+}
+
+/// Tag an image with a keyword, returning the image's tags
+/// Tags are trimmed and lowercased; adding a tag the image already has does nothing.
+#[tauri::command]
+pub async fn add_image_tag(path: String, tag: String) -> Result<Vec<String>, AppError> {
+    let camera_dir = camera_dir()?;
+    let filename = gallery_filename(&camera_dir, &path)?;
+    let tag = normalize_tag(&tag)?;
+
+    let mut tags = load_tags(&camera_dir);
+    let image_tags = tags.entry(filename).or_default();
+    if let Err(i) = image_tags.binary_search(&tag) {
+        image_tags.insert(i, tag);
+    }
+    let image_tags = image_tags.clone();
+    save_tags(&camera_dir, &tags)?;
+    Ok(image_tags)
+}
+
+/// Remove a keyword from an image, returning the image's remaining tags
+#[tauri::command]
+pub async fn remove_image_tag(path: String, tag: String) -> Result<Vec<String>, AppError> {
+    let camera_dir = camera_dir()?;
+    let filename = gallery_filename(&camera_dir, &path)?;
+    let tag = normalize_tag(&tag)?;
+
+    let mut tags = load_tags(&camera_dir);
+    let Some(image_tags) = tags.get_mut(&filename) else {
+        return Ok(Vec::new());
+    };
+    image_tags.retain(|t| *t != tag);
+    let remaining = image_tags.clone();
+    if remaining.is_empty() {
+        tags.remove(&filename);
+    }
+    save_tags(&camera_dir, &tags)?;
+    Ok(remaining)
+}
+
+/// List the images tagged with a keyword, newest first
+#[tauri::command]
+pub async fn list_images_by_tag(tag: String) -> Result<Vec<GalleryImage>, AppError> {
+    let tag = normalize_tag(&tag)?;
+    let mut images = list_gallery_images(None).await?;
+    images.retain(|image| image.tags.contains(&tag));
+    Ok(images)
+}
+
+/// Copy an external image (e.g. from a USB drive) into the gallery
+#[tauri::command]
+pub async fn import_image(source_path: String) -> Result<GalleryImage, AppError> {
+    let source = Path::new(&source_path);
+    if !source.is_file() {
+        return Err(AppError::IoError(format!(
+            "Source file not found: {}",
+            source_path
+        )));
+    }
+
+    // Check the actual contents, not just the extension
+    let format = ImageReader::open(source)
+        .and_then(|reader| reader.with_guessed_format())
+        .map_err(|e| AppError::IoError(format!("Failed to read source image: {}", e)))?
+        .format();
+
+    let ext = match format {
+        Some(ImageFormat::Jpeg) => "jpg",
+        Some(ImageFormat::Png) => "png",
+        _ => {
+            return Err(AppError::InvalidArgument(
+                "Unsupported image type: only JPEG and PNG can be imported".to_string(),
+            ))
+        }
+    };
+
+    let camera_dir = ensure_camera_dir()?;
+
+    let timestamp = Local::now().format("%Y%m%d_%H%M%S").to_string();
+    let target = unique_path(&camera_dir, &format!("IMPORT_{}", timestamp), ext);
+
+    fs::copy(source, &target)
+        .map_err(|e| AppError::IoError(format!("Failed to import image: {}", e)))?;
+
+    Ok(new_gallery_image(&target)?)
+}
+
+/// Describe a freshly written image in the camera directory (never a favorite or tagged yet)
+pub(crate) fn new_gallery_image(path: &Path) -> Result<GalleryImage, String> {
+    let metadata = fs::metadata(path).map_err(|e| format!("Failed to read image: {}", e))?;
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+
+    Ok(GalleryImage {
+        filename: path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        path: path.to_string_lossy().to_string(),
+        size: metadata.len(),
+        modified,
+        extension: path
+            .extension()
+            .map(|e| e.to_string_lossy().to_lowercase())
+            .unwrap_or_default(),
+        favorite: false,
+        tags: Vec::new(),
+    })
+}
+
+/// Start watching the camera directory and emit `gallery-changed` on external changes
+#[tauri::command]
+pub async fn start_gallery_watch(app: AppHandle) -> Result<String, AppError> {
+    if GALLERY_WATCHING.load(Ordering::SeqCst) {
+        return Ok("Gallery watch already running".to_string());
+    }
+
+    // The directory must exist to be watched
+    let camera_dir = ensure_camera_dir()?;
+
+    let (tx, rx) = channel::<notify::Result<Event>>();
+    let mut watcher: RecommendedWatcher = notify::recommended_watcher(tx)
+        .map_err(|e| format!("Failed to create file watcher: {}", e))?;
+    watcher
+        .watch(&camera_dir, RecursiveMode::NonRecursive)
+        .map_err(|e| format!("Failed to watch camera directory: {}", e))?;
+
+    STOP_GALLERY_WATCH.store(false, Ordering::SeqCst);
+    GALLERY_WATCHING.store(true, Ordering::SeqCst);
+
+    thread::spawn(move || {
+        run_gallery_watch(app, watcher, rx);
+    });
+
+    Ok("Gallery watch started".to_string())
+}
+
+/// Stop watching the camera directory
+#[tauri::command]
+pub async fn stop_gallery_watch() -> Result<String, AppError> {
+    if !GALLERY_WATCHING.load(Ordering::SeqCst) {
+        return Ok("Gallery watch not running".to_string());
+    }
+
+    STOP_GALLERY_WATCH.store(true, Ordering::SeqCst);
+
+    // Wait for the watch thread to exit (with timeout)
+    let mut attempts = 0;
+    while GALLERY_WATCHING.load(Ordering::SeqCst) && attempts < 20 {
+        thread::sleep(Duration::from_millis(50));
+        attempts += 1;
+    }
+
+    if GALLERY_WATCHING.load(Ordering::SeqCst) {
+        return Err("Gallery watch failed to stop in time".into());
+    }
+
+    Ok("Gallery watch stopped".to_string())
+}
+
+/// Read the favorites sidecar; a missing or corrupt file means no favorites
+fn load_favorites(camera_dir: &Path) -> HashMap<String, bool> {
+    fs::read(camera_dir.join(FAVORITES_FILE))
+        .ok()
+        .and_then(|data| serde_json::from_slice(&data).ok())
+        .unwrap_or_default()
+}
+
+/// Write the favorites sidecar
+fn save_favorites(camera_dir: &Path, favorites: &HashMap<String, bool>) -> Result<(), String> {
+    let data = serde_json::to_vec_pretty(favorites)
+        .map_err(|e| format!("Failed to encode favorites: {}", e))?;
+    fs::write(camera_dir.join(FAVORITES_FILE), data)
+        .map_err(|e| format!("Failed to save favorites: {}", e))
+}
+
+/// Read the tags sidecar; a missing or corrupt file means no tags
+fn load_tags(camera_dir: &Path) -> HashMap<String, Vec<String>> {
+    fs::read(camera_dir.join(TAGS_FILE))
+        .ok()
+        .and_then(|data| serde_json::from_slice(&data).ok())
+        .unwrap_or_default()
+}
+
+/// Write the tags sidecar
+fn save_tags(camera_dir: &Path, tags: &HashMap<String, Vec<String>>) -> Result<(), String> {
+    let data =
+        serde_json::to_vec_pretty(tags).map_err(|e| format!("Failed to encode tags: {}", e))?;
+    fs::write(camera_dir.join(TAGS_FILE), data).map_err(|e| format!("Failed to save tags: {}", e))
+}
+
+/// Trim and lowercase a tag, rejecting empty ones
+fn normalize_tag(tag: &str) -> Result<String, AppError> {
+    let tag = tag.trim().to_lowercase();
+    if tag.is_empty() {
+        return Err(AppError::InvalidArgument(
+            "Tag must not be empty".to_string(),
+        ));
+    }
+    Ok(tag)
+}
+
+/// Whether `path` is inside the camera directory, without `..` steps leading back out
+pub(crate) fn is_in_camera_dir(camera_dir: &Path, path: &Path) -> bool {
+    let escapes = path.components().any(|c| c == Component::ParentDir);
+    path.starts_with(camera_dir) && !escapes
+}
+
+/// File name of an existing image in the camera directory, the key used by the sidecars
+fn gallery_filename(camera_dir: &Path, path: &str) -> Result<String, AppError> {
+    let target = Path::new(path);
+    if !is_in_camera_dir(camera_dir, target) || !target.is_file() {
+        return Err(AppError::InvalidArgument(format!(
+            "Not a gallery image: {}",
+            path
+        )));
+    }
+    let filename = target
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .ok_or("Image has no file name")?;
+    Ok(filename)
+}
+
+/// Get the camera directory (~/Pictures/honeybee-camera)
+pub(crate) fn camera_dir() -> Result<PathBuf, String> {
+    Ok(pictures_base_dir()?.join(CAMERA_DIR))
+}
+
+/// Get the camera directory, creating it if needed
+pub(crate) fn ensure_camera_dir() -> Result<PathBuf, String> {
+    let camera_dir = camera_dir()?;
+
+    if !camera_dir.exists() {
+        fs::create_dir_all(&camera_dir)
+            .map_err(|e| format!("Failed to create camera directory: {}", e))?;
+    }
+
+    Ok(camera_dir)
+}
+
+/// Watch loop: collapses bursts of events into a single `gallery-changed` emit
+fn run_gallery_watch(
+    app: AppHandle,
+    _watcher: RecommendedWatcher, // dropping the watcher ends the watch
+    rx: Receiver<notify::Result<Event>>,
+) {
+    let mut pending = false;
+
+    loop {
+        if STOP_GALLERY_WATCH.load(Ordering::SeqCst) {
+            break;
+        }
+
+        match rx.recv_timeout(GALLERY_DEBOUNCE) {
+            Ok(Ok(event)) => {
+                let relevant_kind = matches!(
+                    event.kind,
+                    EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+                );
+                // Ignore hidden files (sidecars, temp files)
+                let visible = event.paths.iter().any(|p| {
+                    p.file_name()
+                        .map(|n| !n.to_string_lossy().starts_with('.'))
+                        .unwrap_or(false)
+                });
+                if relevant_kind && visible {
+                    pending = true;
+                }
+            }
+            Ok(Err(e)) => {
+                eprintln!("Gallery watch error: {}", e);
+            }
+            Err(RecvTimeoutError::Timeout) => {
+                if pending {
+                    pending = false;
+                    if let Err(e) = app.emit("gallery-changed", ()) {
+                        eprintln!("Failed to emit gallery change event: {}", e);
+                    }
+                }
+            }
+            Err(RecvTimeoutError::Disconnected) => {
+                break;
+            }
+        }
+    }
+
+    GALLERY_WATCHING.store(false, Ordering::SeqCst);
+    STOP_GALLERY_WATCH.store(false, Ordering::SeqCst);
+}
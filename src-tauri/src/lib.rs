@@ -1,21 +1,75 @@
 mod camera;
 mod commands;
+mod duplicates;
+mod encoders;
+mod encryption;
+mod error;
+mod exif;
+mod faces;
 mod gallery;
+mod media;
+mod multi_camera;
+mod ocr;
+mod permissions;
 mod provisioning_ipc;
 mod recorder;
+mod retention;
+mod session;
+mod sound_trigger;
+mod spectrogram;
 mod system;
+mod upload;
 mod voice_agent_ipc;
+mod wav;
+mod waveform;
 
 #[cfg(debug_assertions)]
 use tauri::Manager;
 
-use camera::{capture_photo, start_camera_stream, stop_camera_stream};
+use camera::{
+    analyze_roi, camera_status, cancel_capture, capture_chroma_key, capture_frame_async,
+    capture_frame_png, capture_gif, capture_hdr, capture_photo, capture_with_timer, detect_motion,
+    dump_ringbuffer, get_camera_control_ranges, get_camera_format, get_last_frame,
+    get_preview_config, is_camera_running, measure_capture_latency, pause_camera, preview_stats,
+    resume_camera, set_preview_config, set_white_balance, start_camera_stream,
+    start_frame_ringbuffer, start_png_sequence, start_timelapse, stop_camera_stream,
+    stop_frame_ringbuffer, stop_png_sequence, stop_timelapse, test_camera,
+};
 use commands::{check_wifi_status, get_qr_code_image, start_qr_file_watcher, trigger_provisioning_retry};
-use gallery::{delete_gallery_image, list_gallery_images, read_gallery_image};
+use duplicates::{find_duplicate_images, image_phash};
+use encryption::set_encryption_key;
+use faces::{detect_faces, suggest_crop};
+use gallery::{
+    add_image_tag, delete_gallery_image, import_image, list_gallery_images, list_images_by_tag,
+    read_gallery_image, read_gallery_image_chunk, remove_image_tag, set_gallery_favorite,
+    start_gallery_watch, stop_gallery_watch,
+};
+use media::{export_file, list_recent_media, media_storage_status};
+use multi_camera::{capture_frames_multi, init_camera_multi, release_camera_multi};
+use ocr::ocr_frame;
+use permissions::{
+    check_camera_permission, check_microphone_permission, request_camera_permission,
+    request_microphone_permission,
+};
 use provisioning_ipc::{check_provisioning_socket, start_provisioning_ipc_listener};
-use recorder::{delete_recording, is_recording, list_recordings, read_audio_file, start_recording, stop_recording};
+use recorder::{
+    audio_system_info, cancel_recording, compress_recording, concat_recordings, delete_recording,
+    get_recording_gain, get_recording_name_template, import_recording, is_recording,
+    list_input_sample_formats, list_loopback_devices, list_recordings, list_recordings_grouped,
+    read_audio_file, read_audio_range, read_encrypted_recording, recover_recording_on_exit,
+    refresh_audio_devices, repair_wav, resample_recording, reset_recording_index,
+    set_input_sample_format, set_recording_gain, set_recording_metadata,
+    set_recording_name_template, start_recording, stop_recording, trim_recording,
+    validate_recording,
+};
+use retention::{get_retention_policy, set_retention_policy};
+use session::reset_all_devices;
+use sound_trigger::{start_sound_triggered_capture, stop_sound_triggered_capture};
+use spectrogram::generate_spectrogram;
 use system::{get_brightness, set_brightness, get_volume, set_volume};
+use upload::{upload_frame, upload_recording};
 use voice_agent_ipc::{check_voice_agent_socket, start_voice_agent_ipc_listener};
+use waveform::generate_waveform_image;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
@@ -38,18 +92,107 @@ pub fn run() {
             // Camera commands
             start_camera_stream,
             stop_camera_stream,
+            pause_camera,
+            resume_camera,
+            set_white_balance,
+            capture_hdr,
+            capture_gif,
+            init_camera_multi,
+            capture_frames_multi,
+            release_camera_multi,
+            is_camera_running,
+            get_camera_format,
+            get_camera_control_ranges,
+            preview_stats,
+            measure_capture_latency,
+            set_preview_config,
+            get_preview_config,
+            test_camera,
+            camera_status,
             capture_photo,
+            capture_frame_png,
+            capture_frame_async,
+            capture_with_timer,
+            cancel_capture,
+            get_last_frame,
+            capture_chroma_key,
+            detect_motion,
+            analyze_roi,
+            ocr_frame,
+            start_timelapse,
+            stop_timelapse,
+            start_png_sequence,
+            stop_png_sequence,
+            start_frame_ringbuffer,
+            stop_frame_ringbuffer,
+            dump_ringbuffer,
+            start_sound_triggered_capture,
+            stop_sound_triggered_capture,
+            detect_faces,
+            suggest_crop,
             // Gallery commands
             list_gallery_images,
             read_gallery_image,
+            read_gallery_image_chunk,
             delete_gallery_image,
+            import_image,
+            start_gallery_watch,
+            stop_gallery_watch,
+            set_gallery_favorite,
+            add_image_tag,
+            remove_image_tag,
+            list_images_by_tag,
+            image_phash,
+            find_duplicate_images,
             // Recorder commands
             start_recording,
             stop_recording,
+            cancel_recording,
             list_recordings,
+            list_recordings_grouped,
+            import_recording,
             read_audio_file,
+            read_audio_range,
+            read_encrypted_recording,
+            set_encryption_key,
             delete_recording,
-            is_recording
+            set_recording_metadata,
+            is_recording,
+            set_recording_gain,
+            get_recording_gain,
+            list_input_sample_formats,
+            list_loopback_devices,
+            set_input_sample_format,
+            set_recording_name_template,
+            get_recording_name_template,
+            reset_recording_index,
+            trim_recording,
+            validate_recording,
+            resample_recording,
+            compress_recording,
+            concat_recordings,
+            repair_wav,
+            generate_waveform_image,
+            generate_spectrogram,
+            refresh_audio_devices,
+            audio_system_info,
+            // Media overview (combined feed, storage, export)
+            list_recent_media,
+            media_storage_status,
+            export_file,
+            // Automatic cleanup of old photos and recordings
+            set_retention_policy,
+            get_retention_policy,
+            // Device access checks
+            check_microphone_permission,
+            request_microphone_permission,
+            check_camera_permission,
+            request_camera_permission,
+            // Session teardown
+            reset_all_devices,
+            // Uploads to the backend
+            upload_frame,
+            upload_recording
         ])
         .setup(|app| {
             // Open devtools only in debug builds
@@ -91,6 +234,12 @@ pub fn run() {
 
             Ok(())
         })
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|_app, event| {
+            // Don't lose an in-progress recording when the app is closed
+            if let tauri::RunEvent::Exit = event {
+                recover_recording_on_exit();
+            }
+        });
 }
@@ -0,0 +1,262 @@
+use crate::error::AppError;
+use crate::gallery::{camera_dir, list_gallery_images};
+use crate::recorder::{list_recordings, recordings_dir};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+// Environment overrides for the media base directories
+const PICTURES_DIR_ENV: &str = "HONEYBEE_PICTURES_DIR";
+const MUSIC_DIR_ENV: &str = "HONEYBEE_MUSIC_DIR";
+
+/// Photo or recording entry for the combined media feed
+#[derive(Clone, serde::Serialize)]
+pub struct MediaItem {
+    pub media_type: String, // "image" or "audio"
+    pub filename: String,
+    pub path: String,
+    pub size: u64,
+    pub modified: u64, // unix timestamp
+}
+
+/// Space on the filesystem backing a media directory
+#[derive(Clone, serde::Serialize)]
+pub struct VolumeStatus {
+    pub path: String,
+    pub total_bytes: u64,
+    pub available_bytes: u64,
+    pub used_bytes: u64,
+}
+
+/// Storage overview for the photo and recording directories
+#[derive(Clone, serde::Serialize)]
+pub struct StorageStatus {
+    pub pictures: VolumeStatus,
+    pub music: VolumeStatus,
+    pub camera_dir_bytes: u64,
+    pub recordings_dir_bytes: u64,
+}
+
+/// List the most recent photos and recordings, interleaved newest first
+#[tauri::command]
+pub async fn list_recent_media(limit: usize) -> Result<Vec<MediaItem>, AppError> {
+    let images = list_gallery_images(None).await?;
+    let recordings = list_recordings().await?;
+
+    let mut items: Vec<MediaItem> = Vec::with_capacity(images.len() + recordings.len());
+
+    items.extend(images.into_iter().map(|img| MediaItem {
+        media_type: "image".to_string(),
+        filename: img.filename,
+        path: img.path,
+        size: img.size,
+        modified: img.modified,
+    }));
+
+    items.extend(recordings.into_iter().map(|rec| MediaItem {
+        media_type: "audio".to_string(),
+        filename: rec.filename,
+        path: rec.path,
+        size: rec.size,
+        modified: rec.modified,
+    }));
+
+    // Sort newest first
+    items.sort_by_key(|item| std::cmp::Reverse(item.modified));
+    items.truncate(limit);
+
+    Ok(items)
+}
+
+/// Report free space for the Pictures and Music filesystems and our directories' usage
+#[tauri::command]
+pub async fn media_storage_status() -> Result<StorageStatus, AppError> {
+    let camera_dir = camera_dir()?;
+    let recordings_dir = recordings_dir()?;
+
+    Ok(StorageStatus {
+        pictures: volume_status(&camera_dir)?,
+        music: volume_status(&recordings_dir)?,
+        camera_dir_bytes: dir_size(&camera_dir),
+        recordings_dir_bytes: dir_size(&recordings_dir),
+    })
+}
+
+/// Copy a photo or recording out to `dest` (e.g. a USB drive), returning the path written
+/// If `dest` is an existing directory the file keeps its name inside it.
+#[tauri::command]
+pub async fn export_file(source: String, dest: String) -> Result<String, AppError> {
+    let source = Path::new(&source);
+    if !is_managed_media(source)? {
+        return Err(AppError::PathOutsideAllowedDir(
+            "Cannot export files outside the photo and recording directories".to_string(),
+        ));
+    }
+    if !source.is_file() {
+        return Err(AppError::IoError(format!(
+            "Source file not found: {:?}",
+            source
+        )));
+    }
+
+    let mut target = PathBuf::from(&dest);
+    if target.is_dir() {
+        let filename = source.file_name().ok_or("Source has no file name")?;
+        target = target.join(filename);
+    }
+
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| AppError::IoError(format!("Failed to create export directory: {}", e)))?;
+    }
+
+    fs::copy(source, &target)
+        .map_err(|e| AppError::IoError(format!("Failed to export file: {}", e)))?;
+    Ok(target.to_string_lossy().to_string())
+}
+
+/// Check that a path points inside the camera or recordings directory
+fn is_managed_media(path: &Path) -> Result<bool, String> {
+    if path.components().any(|c| c == Component::ParentDir) {
+        return Ok(false);
+    }
+    Ok(path.starts_with(camera_dir()?) || path.starts_with(recordings_dir()?))
+}
+
+/// Query the filesystem holding `path` (or its nearest existing parent)
+fn volume_status(path: &Path) -> Result<VolumeStatus, String> {
+    let existing = path
+        .ancestors()
+        .find(|p| p.exists())
+        .ok_or_else(|| format!("No existing directory for {:?}", path))?;
+
+    let total_bytes = fs2::total_space(existing)
+        .map_err(|e| format!("Failed to query disk space for {:?}: {}", existing, e))?;
+    let available_bytes = fs2::available_space(existing)
+        .map_err(|e| format!("Failed to query disk space for {:?}: {}", existing, e))?;
+
+    Ok(VolumeStatus {
+        path: existing.to_string_lossy().to_string(),
+        total_bytes,
+        available_bytes,
+        used_bytes: total_bytes.saturating_sub(available_bytes),
+    })
+}
+
+/// Total size of all files under `dir` (0 if it doesn't exist)
+fn dir_size(dir: &Path) -> u64 {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return 0,
+    };
+
+    entries
+        .flatten()
+        .map(|entry| match entry.metadata() {
+            Ok(metadata) if metadata.is_dir() => dir_size(&entry.path()),
+            Ok(metadata) => metadata.len(),
+            Err(_) => 0,
+        })
+        .sum()
+}
+
+/// Pick a file path in `dir` that doesn't exist yet, appending _1, _2, ... if needed
+pub(crate) fn unique_path(dir: &Path, stem: &str, ext: &str) -> PathBuf {
+    let mut candidate = dir.join(format!("{}.{}", stem, ext));
+    let mut index = 1;
+    while candidate.exists() {
+        candidate = dir.join(format!("{}_{}.{}", stem, index, ext));
+        index += 1;
+    }
+    candidate
+}
+
+/// Base directory for photos (created if missing)
+pub(crate) fn pictures_base_dir() -> Result<PathBuf, String> {
+    resolve_base_dir("Pictures", PICTURES_DIR_ENV, dirs::picture_dir())
+}
+
+/// Base directory for recordings (created if missing)
+pub(crate) fn music_base_dir() -> Result<PathBuf, String> {
+    resolve_base_dir("Music", MUSIC_DIR_ENV, dirs::audio_dir())
+}
+
+/// Pick the first usable base directory: `env_var` override, then the XDG dir, then ~/<name>
+/// Stripped-down systems may have no XDG dirs at all, or point them at paths that don't exist.
+fn resolve_base_dir(
+    name: &str,
+    env_var: &str,
+    xdg_dir: Option<PathBuf>,
+) -> Result<PathBuf, String> {
+    let candidates = [
+        std::env::var_os(env_var)
+            .filter(|v| !v.is_empty())
+            .map(PathBuf::from),
+        xdg_dir,
+        dirs::home_dir().map(|home| home.join(name)),
+    ];
+
+    let mut tried = Vec::new();
+    for dir in candidates.into_iter().flatten() {
+        match fs::create_dir_all(&dir) {
+            Ok(()) => return Ok(dir),
+            Err(e) => tried.push(format!("{:?} ({})", dir, e)),
+        }
+    }
+
+    if tried.is_empty() {
+        Err(format!(
+            "No {} directory found: set {} or HOME",
+            name, env_var
+        ))
+    } else {
+        Err(format!(
+            "Failed to create a {} directory, tried: {}",
+            name,
+            tried.join(", ")
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Fresh directory under the system temp dir, unique to this test
+    fn scratch_dir(test: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("honeybee-{}-{}", test, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn resolve_base_dir_without_override_or_xdg_uses_home() {
+        // Joining an absolute name onto home yields the name itself, keeping the test out of ~
+        let fallback = scratch_dir("unset");
+        let name = fallback.to_str().unwrap();
+
+        let dir = resolve_base_dir(name, "HONEYBEE_TEST_UNSET_DIR", None).unwrap();
+        assert_eq!(dir, fallback);
+        assert!(dir.is_dir());
+        fs::remove_dir_all(&fallback).unwrap();
+    }
+
+    #[test]
+    fn resolve_base_dir_falls_back_when_xdg_dir_is_unusable() {
+        let root = scratch_dir("fallback");
+        fs::create_dir_all(&root).unwrap();
+        // A directory can't be created below a regular file
+        let blocker = root.join("file");
+        fs::write(&blocker, b"").unwrap();
+        let fallback = root.join("home");
+
+        let dir = resolve_base_dir(
+            fallback.to_str().unwrap(),
+            "HONEYBEE_TEST_FALLBACK_DIR",
+            Some(blocker.join("Pictures")),
+        )
+        .unwrap();
+        assert_eq!(dir, fallback);
+        assert!(dir.is_dir());
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
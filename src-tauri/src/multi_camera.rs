@@ -0,0 +1,202 @@
+//! Synchronized captures from several cameras
+//!
+//! Camera 0 is always the main stream, so the single-camera commands keep working
+//! alongside. Every other camera gets a worker thread that keeps its latest frame in
+//! memory without emitting previews. A multi capture takes the newest frame from
+//! each camera in one pass, so the set is at most about one frame interval apart.
+
+use crate::camera::{
+    default_resolution, encode_jpeg, is_camera_running, latest_frame_rgb, open_camera,
+    requested_format, start_camera_stream, stream_kind, CameraFrame, StreamPixelFormat,
+};
+use crate::error::AppError;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use image::RgbImage;
+use nokhwa::{pixel_format::RgbFormat, utils::ApiBackend};
+use parking_lot::RwLock;
+use std::{
+    collections::BTreeMap,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        mpsc::channel,
+    },
+    thread,
+    time::Duration,
+};
+use tauri::AppHandle;
+
+static STOP_MULTI_CAMERAS: AtomicBool = AtomicBool::new(false);
+// Secondary camera worker threads still running
+static MULTI_CAMERA_WORKERS: AtomicUsize = AtomicUsize::new(0);
+
+lazy_static::lazy_static! {
+    // Cameras in the current multi-camera set, in ascending order
+    static ref MULTI_CAMERA_INDICES: RwLock<Vec<u32>> = RwLock::new(Vec::new());
+    // Latest frame from each secondary camera (everything except camera 0)
+    static ref SECONDARY_FRAMES: RwLock<BTreeMap<u32, RgbImage>> = RwLock::new(BTreeMap::new());
+}
+
+/// Open the cameras at `indices` for capture_frames_multi
+/// Camera 0 is the main stream and is started with default settings if it isn't running.
+/// The others are opened at the default resolution; if any fails, none are kept open.
+#[tauri::command]
+pub async fn init_camera_multi(app: AppHandle, mut indices: Vec<u32>) -> Result<String, AppError> {
+    if !MULTI_CAMERA_INDICES.read().is_empty() {
+        return Err("Multi-camera capture already running".into());
+    }
+
+    indices.sort_unstable();
+    indices.dedup();
+    if indices.is_empty() {
+        return Err(AppError::InvalidArgument("No cameras given".to_string()));
+    }
+
+    if indices.contains(&0) && !is_camera_running().await? {
+        start_camera_stream(app, None, None, None, None, None, None).await?;
+    }
+
+    // Open every secondary camera before reporting back, so a bad index fails the call
+    STOP_MULTI_CAMERAS.store(false, Ordering::SeqCst);
+    let (opened_tx, opened) = channel();
+    let secondary: Vec<u32> = indices.iter().copied().filter(|&i| i != 0).collect();
+    for &index in &secondary {
+        let opened_tx = opened_tx.clone();
+        MULTI_CAMERA_WORKERS.fetch_add(1, Ordering::SeqCst);
+        thread::spawn(move || {
+            run_secondary_camera(index, |result| {
+                let _ = opened_tx.send((index, result));
+            });
+        });
+    }
+    drop(opened_tx);
+
+    let errors: Vec<String> = opened
+        .iter()
+        .filter_map(|(index, result)| result.err().map(|e| format!("camera {}: {}", index, e)))
+        .collect();
+    if !errors.is_empty() {
+        stop_secondary_cameras()?;
+        return Err(format!("Failed to open cameras: {}", errors.join("; ")).into());
+    }
+
+    *MULTI_CAMERA_INDICES.write() = indices;
+    Ok("Multi-camera capture started".to_string())
+}
+
+/// Capture one frame from each camera opened by init_camera_multi, in ascending index order
+#[tauri::command]
+pub async fn capture_frames_multi() -> Result<Vec<CameraFrame>, AppError> {
+    let indices = MULTI_CAMERA_INDICES.read().clone();
+    if indices.is_empty() {
+        return Err("Multi-camera capture not running".into());
+    }
+
+    // Grab every frame first and encode afterwards, keeping the set close in time
+    let frames: Vec<RgbImage> = {
+        let secondary = SECONDARY_FRAMES.read();
+        indices
+            .iter()
+            .map(|&index| match index {
+                0 => latest_frame_rgb(),
+                _ => secondary.get(&index).cloned().ok_or_else(|| {
+                    let message = format!("No frame available from camera {}", index);
+                    AppError::CameraNotInitialized(message)
+                }),
+            })
+            .collect::<Result<_, _>>()?
+    };
+
+    // Secondary cameras always stream colour; camera 0 may be an infrared stream
+    let main_stream = stream_kind();
+    tauri::async_runtime::spawn_blocking(move || {
+        indices
+            .iter()
+            .zip(&frames)
+            .map(|(&index, frame)| {
+                let jpeg = encode_jpeg(frame)?;
+                Ok(CameraFrame {
+                    data: format!("data:image/jpeg;base64,{}", STANDARD.encode(&jpeg)),
+                    width: frame.width(),
+                    height: frame.height(),
+                    format: "jpeg".to_string(),
+                    stream: match index {
+                        0 => main_stream.clone(),
+                        _ => "color".to_string(),
+                    },
+                })
+            })
+            .collect()
+    })
+    .await
+    .map_err(|e| format!("Failed to capture frames: {}", e))?
+}
+
+/// Close the secondary cameras opened by init_camera_multi
+/// The main stream (camera 0) is left running; stop it with stop_camera_stream.
+#[tauri::command]
+pub async fn release_camera_multi() -> Result<String, AppError> {
+    if MULTI_CAMERA_INDICES.read().is_empty() {
+        return Ok("Multi-camera capture not running".to_string());
+    }
+
+    stop_secondary_cameras()?;
+    MULTI_CAMERA_INDICES.write().clear();
+    Ok("Multi-camera capture stopped".to_string())
+}
+
+/// Signal every secondary camera worker to stop and wait for them to exit
+fn stop_secondary_cameras() -> Result<(), String> {
+    STOP_MULTI_CAMERAS.store(true, Ordering::SeqCst);
+
+    // Wait for the worker threads to finish (with timeout)
+    let mut attempts = 0;
+    while MULTI_CAMERA_WORKERS.load(Ordering::SeqCst) > 0 && attempts < 50 {
+        thread::sleep(Duration::from_millis(50));
+        attempts += 1;
+    }
+
+    if MULTI_CAMERA_WORKERS.load(Ordering::SeqCst) > 0 {
+        return Err("Cameras failed to stop in time".to_string());
+    }
+
+    STOP_MULTI_CAMERAS.store(false, Ordering::SeqCst);
+    Ok(())
+}
+
+/// Worker for one secondary camera: keeps its latest RGB frame in SECONDARY_FRAMES
+/// `opened` is called once with the result of opening the camera.
+fn run_secondary_camera(index: u32, opened: impl FnOnce(Result<(), String>)) {
+    let requested = requested_format(StreamPixelFormat::Rgb, default_resolution());
+    let mut camera = match open_camera(index, requested, ApiBackend::Auto) {
+        Ok(camera) => {
+            opened(Ok(()));
+            camera
+        }
+        Err(e) => {
+            opened(Err(e));
+            MULTI_CAMERA_WORKERS.fetch_sub(1, Ordering::SeqCst);
+            return;
+        }
+    };
+
+    // frame() blocks until the next frame, which paces the loop
+    while !STOP_MULTI_CAMERAS.load(Ordering::SeqCst) {
+        let frame = camera
+            .frame()
+            .and_then(|frame| frame.decode_image::<RgbFormat>());
+        match frame {
+            Ok(decoded) => {
+                let mut guard = SECONDARY_FRAMES.write();
+                guard.insert(index, decoded);
+            }
+            Err(e) => {
+                eprintln!("Camera {} frame error: {}", index, e);
+                thread::sleep(Duration::from_millis(50));
+            }
+        }
+    }
+
+    let _ = camera.stop_stream();
+    SECONDARY_FRAMES.write().remove(&index);
+    MULTI_CAMERA_WORKERS.fetch_sub(1, Ordering::SeqCst);
+}
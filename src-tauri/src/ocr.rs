@@ -0,0 +1,88 @@
+//! Text recognition on the live camera frame
+//!
+//! Runs the `tesseract` CLI (install `tesseract-ocr` plus a language pack such
+//! as `tesseract-ocr-eng` on the kiosk image) with the frame piped in as a
+//! grayscale PNG. Language data is looked up in this order:
+//! - `resources/tessdata/` in the app's resource directory, if present (add the
+//!   `*.traineddata` files there and list them in `bundle.resources` in
+//!   tauri.conf.json to ship them with the app)
+//! - tesseract's own default (`TESSDATA_PREFIX` or the system tessdata dir)
+
+use crate::camera::latest_frame_rgb;
+use crate::error::AppError;
+use image::{DynamicImage, ImageFormat};
+use std::io::{Cursor, Write};
+use std::process::{Command, Stdio};
+use tauri::{path::BaseDirectory, AppHandle, Manager};
+
+const TESSDATA_RESOURCE: &str = "resources/tessdata";
+const DEFAULT_OCR_LANGUAGE: &str = "eng";
+
+/// Recognize text in the current camera frame (empty string if none is found)
+/// `language` is a tesseract language code such as "eng" or "eng+deu".
+#[tauri::command]
+pub async fn ocr_frame(app: AppHandle, language: Option<String>) -> Result<String, AppError> {
+    let language = language.unwrap_or_else(|| DEFAULT_OCR_LANGUAGE.to_string());
+    if language.is_empty()
+        || !language
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '+')
+    {
+        return Err(AppError::InvalidArgument(format!(
+            "Invalid OCR language: {}",
+            language
+        )));
+    }
+
+    // Grayscale PNG keeps the pipe small and is what tesseract works on anyway
+    let frame = latest_frame_rgb()?;
+    let gray = DynamicImage::ImageRgb8(frame).to_luma8();
+    let mut png = Cursor::new(Vec::new());
+    gray.write_to(&mut png, ImageFormat::Png)
+        .map_err(|e| format!("Failed to encode PNG: {}", e))?;
+    let png = png.into_inner();
+
+    // Prefer bundled language data when the app ships it
+    let tessdata_dir = app
+        .path()
+        .resolve(TESSDATA_RESOURCE, BaseDirectory::Resource)
+        .ok()
+        .filter(|dir| dir.is_dir());
+
+    let text = tauri::async_runtime::spawn_blocking(move || {
+        let mut command = Command::new("tesseract");
+        command.args(["stdin", "stdout", "-l", &language]);
+        if let Some(dir) = &tessdata_dir {
+            command.arg("--tessdata-dir").arg(dir);
+        }
+
+        let mut child = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("Failed to run tesseract: {}", e))?;
+
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin
+                .write_all(&png)
+                .map_err(|e| format!("Failed to send frame to tesseract: {}", e))?;
+        }
+
+        let output = child
+            .wait_with_output()
+            .map_err(|e| format!("Failed to run tesseract: {}", e))?;
+
+        if !output.status.success() {
+            return Err(format!(
+                "tesseract failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    })
+    .await
+    .map_err(|e| format!("Failed to run OCR: {}", e))??;
+    Ok(text)
+}
@@ -0,0 +1,155 @@
+//! Best-effort microphone and camera access checks
+//!
+//! Linux has no OS permission prompt for audio or video, so access is inferred:
+//! - microphone: briefly open the default input; a device that can't be opened,
+//!   or that only ever delivers exact zeros, is treated as blocked
+//! - camera: enumerate cameras; if none show up but /dev/video* nodes exist and
+//!   can't be opened, access is blocked
+
+use crate::camera::is_camera_running;
+use crate::error::AppError;
+use crate::recorder::{build_f32_input_stream, choose_input_config, is_recording};
+use cpal::traits::{HostTrait, StreamTrait};
+use nokhwa::utils::ApiBackend;
+use std::{
+    fs::{self, OpenOptions},
+    io::ErrorKind,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+// How long to listen to the microphone when probing access
+const MIC_PROBE_DURATION_MS: u64 = 300;
+
+/// Result of a permission check
+#[derive(Clone, Copy, PartialEq, serde::Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PermissionStatus {
+    Granted,
+    Denied,
+    Undetermined,
+}
+
+/// Check whether the app can capture audio from the microphone
+#[tauri::command]
+pub async fn check_microphone_permission() -> Result<PermissionStatus, AppError> {
+    // An active recording already proves access (and holds the device)
+    if is_recording().await? {
+        return Ok(PermissionStatus::Granted);
+    }
+
+    tauri::async_runtime::spawn_blocking(probe_microphone)
+        .await
+        .map_err(|e| format!("Failed to probe microphone: {}", e).into())
+}
+
+/// Ask for microphone access
+/// There is no prompt to trigger on Linux; opening the device is the request,
+/// so this runs the same probe and reports the outcome.
+#[tauri::command]
+pub async fn request_microphone_permission() -> Result<PermissionStatus, AppError> {
+    check_microphone_permission().await
+}
+
+/// Check whether the app can open a camera
+#[tauri::command]
+pub async fn check_camera_permission() -> Result<PermissionStatus, AppError> {
+    // A running stream already proves access (and holds the device)
+    if is_camera_running().await? {
+        return Ok(PermissionStatus::Granted);
+    }
+
+    tauri::async_runtime::spawn_blocking(probe_camera)
+        .await
+        .map_err(|e| format!("Failed to probe camera: {}", e).into())
+}
+
+/// Ask for camera access
+/// As with the microphone there is no prompt on Linux, so this reports the probe result.
+#[tauri::command]
+pub async fn request_camera_permission() -> Result<PermissionStatus, AppError> {
+    check_camera_permission().await
+}
+
+/// Enumerate cameras, then look at the device nodes if none are visible
+fn probe_camera() -> PermissionStatus {
+    if nokhwa::query(ApiBackend::Auto).is_ok_and(|cameras| !cameras.is_empty()) {
+        return PermissionStatus::Granted;
+    }
+
+    let nodes: Vec<_> = match fs::read_dir("/dev") {
+        Ok(entries) => entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.file_name()
+                    .is_some_and(|name| name.to_string_lossy().starts_with("video"))
+            })
+            .collect(),
+        Err(_) => return PermissionStatus::Undetermined,
+    };
+
+    // Nodes we can't open mean the cameras are there but blocked
+    let denied = nodes.iter().any(|node| {
+        OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(node)
+            .is_err_and(|e| e.kind() == ErrorKind::PermissionDenied)
+    });
+
+    if denied {
+        PermissionStatus::Denied
+    } else {
+        PermissionStatus::Undetermined
+    }
+}
+
+/// Open the default input briefly and see whether real audio arrives
+fn probe_microphone() -> PermissionStatus {
+    // No device can mean missing hardware as well as blocked access
+    let device = match cpal::default_host().default_input_device() {
+        Some(d) => d,
+        None => return PermissionStatus::Undetermined,
+    };
+
+    let config = match choose_input_config(&device, None) {
+        Ok(c) => c,
+        Err(_) => return PermissionStatus::Denied,
+    };
+
+    let got_samples = Arc::new(AtomicBool::new(false));
+    let got_signal = Arc::new(AtomicBool::new(false));
+    let (samples_flag, signal_flag) = (got_samples.clone(), got_signal.clone());
+
+    let stream = match build_f32_input_stream(&device, &config, move |data| {
+        if !data.is_empty() {
+            samples_flag.store(true, Ordering::SeqCst);
+        }
+        // Real microphones always pick up some noise; a blocked one reads exact zeros
+        if data.iter().any(|&s| s != 0.0) {
+            signal_flag.store(true, Ordering::SeqCst);
+        }
+    }) {
+        Ok(s) => s,
+        Err(_) => return PermissionStatus::Denied,
+    };
+
+    if stream.play().is_err() {
+        return PermissionStatus::Denied;
+    }
+    thread::sleep(Duration::from_millis(MIC_PROBE_DURATION_MS));
+    drop(stream);
+
+    if got_signal.load(Ordering::SeqCst) {
+        PermissionStatus::Granted
+    } else if got_samples.load(Ordering::SeqCst) {
+        PermissionStatus::Denied
+    } else {
+        PermissionStatus::Undetermined
+    }
+}
@@ -1,8 +1,22 @@
+use crate::encoders::{encode_flac, encode_mp3, encode_ogg};
+use crate::encryption::{
+    decrypt, encrypt, encryption_enabled, is_encrypted_path, ENCRYPTED_EXTENSION,
+};
+use crate::error::AppError;
+use crate::media::{music_base_dir, unique_path};
+use crate::retention::enforce_retention;
+use crate::waveform::remove_cached_thumbnails;
+use crate::wav::{
+    decode_wav, encode_wav, ms_to_samples, read_wav, read_wav_duration_ms, read_wav_range,
+    repair_wav_header, samples_to_ms, WavSampleFormat, WavWriter,
+};
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
-use chrono::Local;
+use chrono::{DateTime, Local, NaiveDate};
 use std::{
+    collections::{HashMap, VecDeque},
     fs,
-    io::{Cursor, Write},
+    io::Read,
+    path::{Component, Path, PathBuf},
     sync::{
         atomic::{AtomicBool, Ordering},
         Arc,
@@ -11,17 +25,85 @@ use std::{
     time::{Duration, Instant},
 };
 use parking_lot::Mutex;
+use rubato::{FftFixedIn, Resampler};
 use tauri::{AppHandle, Emitter};
 
 const RECORDINGS_DIR: &str = "honeybee-recordings";
 
+// Sidecar in the recordings directory mapping filenames to their metadata
+const METADATA_FILE: &str = ".metadata.json";
+
+// Filename templates (without extension); see render_name_template for placeholders
+const DEFAULT_NAME_TEMPLATE: &str = "REC_{timestamp}";
+const RECOVERED_NAME_TEMPLATE: &str = "RECOVERED_{timestamp}";
+const MAX_FILENAME_LEN: usize = 200;
+
+// Upper bound for software input gain (about +18 dB)
+const MAX_RECORDING_GAIN: f32 = 8.0;
+
+// Peak normalization target (-1 dBFS) and the level below which audio is treated as silence
+const NORMALIZE_TARGET_PEAK: f32 = 0.891;
+const SILENCE_PEAK: f32 = 0.001;
+
+// Samples at or above this level count as clipped, and the share of them that marks a
+// recording as clipped; levels are reported down to LEVEL_FLOOR_DB
+const CLIP_LEVEL: f32 = 0.999;
+const MAX_CLIPPED_FRACTION: f32 = 0.001;
+const LEVEL_FLOOR_DB: f32 = -120.0;
+
+// Longest duration hint we'll pre-allocate sample memory for (10 minutes)
+const MAX_PREALLOC_DURATION_MS: u64 = 10 * 60 * 1000;
+
+// Recordings expected to run longer than this stream to disk by default (5 minutes)
+const STREAM_TO_DISK_THRESHOLD_MS: u64 = 5 * 60 * 1000;
+
+// Most audio the monitor may lag behind the microphone before old samples are dropped
+const MONITOR_MAX_LATENCY_MS: u64 = 100;
+
+// Peaks sent per live waveform update
+const WAVEFORM_POINTS: usize = 32;
+
+// How often the input device is re-queried for a renegotiated format while recording
+const CONFIG_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+// Accepted target rates for resample_recording, and the resampler's input chunk size
+const MIN_RESAMPLE_RATE: u32 = 8_000;
+const MAX_RESAMPLE_RATE: u32 = 192_000;
+const RESAMPLE_CHUNK_FRAMES: usize = 1024;
+
+// Whether the platform can record what's playing: WASAPI opens output devices in
+// loopback mode, and on Linux the snd-aloop card appears as an input. cpal's ALSA host
+// doesn't list PulseAudio/PipeWire monitor sources; one only shows up once it's exposed
+// as an ALSA PCM whose name contains "monitor" (an asoundrc entry). CoreAudio has no
+// native loopback.
+const LOOPBACK_SUPPORTED: bool = cfg!(any(target_os = "windows", target_os = "linux"));
+// Name fragments (lowercase) that mark a Linux input device as a loopback source
+const LOOPBACK_NAME_HINTS: [&str; 2] = ["monitor", "loopback"];
+
+// Audio file extensions shown in the recordings list
+const AUDIO_EXTENSIONS: [&str; 4] = ["wav", "mp3", "flac", "ogg"];
+
 static RECORDING: AtomicBool = AtomicBool::new(false);
 static STOP_RECORDING: AtomicBool = AtomicBool::new(false);
+static COUNTDOWN_ACTIVE: AtomicBool = AtomicBool::new(false);
+static STREAMING_TO_DISK: AtomicBool = AtomicBool::new(false);
 
 lazy_static::lazy_static! {
     static ref RECORDING_SAMPLES: Arc<Mutex<Vec<f32>>> = Arc::new(Mutex::new(Vec::new()));
     static ref SAMPLE_RATE: Arc<Mutex<u32>> = Arc::new(Mutex::new(44100));
     static ref CHANNELS: Arc<Mutex<u16>> = Arc::new(Mutex::new(1));
+    static ref RECORDING_GAIN: Arc<Mutex<f32>> = Arc::new(Mutex::new(1.0));
+    // Sample format to capture in; None uses the device default when it's supported
+    static ref INPUT_SAMPLE_FORMAT: Mutex<Option<InputSampleFormat>> = Mutex::new(None);
+    // Outcome of the last streamed recording, collected by stop_recording
+    static ref STREAMED_RESULT: Mutex<Option<RecordingSaved>> = Mutex::new(None);
+    // Filename template for new recordings, persisted across restarts
+    static ref NAME_TEMPLATE: Mutex<String> = Mutex::new(load_name_template());
+    // Last {session} number used (0 after a reset), persisted across restarts; held while
+    // a recording's name is picked so concurrent saves never share a number
+    static ref SESSION_INDEX: Mutex<u32> = Mutex::new(load_session_index());
+    // Parsed durations keyed by path, valid while (modified, size) match
+    static ref DURATION_CACHE: Mutex<HashMap<PathBuf, (u64, u64, u64)>> = Mutex::new(HashMap::new());
 }
 
 #[derive(Clone, serde::Serialize)]
@@ -30,6 +112,129 @@ pub struct RecordingStatus {
     pub duration_ms: u64,
 }
 
+/// Live waveform slice: peak levels (0.0-1.0) of the audio since the last update
+#[derive(Clone, serde::Serialize)]
+pub struct RecordingWaveform {
+    pub peaks: Vec<f32>,
+}
+
+/// Input format change event payload; `channels`/`sample_rate` are None if the
+/// device couldn't be queried
+#[derive(Clone, serde::Serialize)]
+pub struct RecordingConfigChanged {
+    pub expected_channels: u16,
+    pub expected_sample_rate: u32,
+    pub channels: Option<u16>,
+    pub sample_rate: Option<u32>,
+    pub duration_ms: u64, // audio captured before the change
+}
+
+/// Sample types recordings can be captured in; all are converted to f32 on input
+#[derive(Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InputSampleFormat {
+    F32,
+    I16,
+    U16,
+}
+
+impl InputSampleFormat {
+    // Preference order when the device default can't be used
+    const ALL: [InputSampleFormat; 3] = [
+        InputSampleFormat::F32,
+        InputSampleFormat::I16,
+        InputSampleFormat::U16,
+    ];
+
+    fn from_cpal(format: cpal::SampleFormat) -> Option<Self> {
+        match format {
+            cpal::SampleFormat::F32 => Some(InputSampleFormat::F32),
+            cpal::SampleFormat::I16 => Some(InputSampleFormat::I16),
+            cpal::SampleFormat::U16 => Some(InputSampleFormat::U16),
+            _ => None,
+        }
+    }
+
+    fn to_cpal(self) -> cpal::SampleFormat {
+        match self {
+            InputSampleFormat::F32 => cpal::SampleFormat::F32,
+            InputSampleFormat::I16 => cpal::SampleFormat::I16,
+            InputSampleFormat::U16 => cpal::SampleFormat::U16,
+        }
+    }
+}
+
+/// Format a recording is being captured in, sent once its input stream is open
+#[derive(Clone, serde::Serialize)]
+pub struct RecordingFormat {
+    pub sample_format: InputSampleFormat,
+    pub sample_rate: u32,
+    pub channels: u16,
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct RecordingCountdown {
+    pub seconds_left: u64,
+    pub remaining_ms: u64,
+}
+
+/// Compression progress event payload
+#[derive(Clone, serde::Serialize)]
+pub struct CompressProgress {
+    pub path: String,  // recording being compressed
+    pub progress: f32, // 0.0-1.0
+}
+
+/// Formats compress_recording can produce
+#[derive(Clone, Copy, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CompressFormat {
+    Mp3,
+    Ogg,
+    Flac,
+}
+
+impl CompressFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            CompressFormat::Mp3 => "mp3",
+            CompressFormat::Ogg => "ogg",
+            CompressFormat::Flac => "flac",
+        }
+    }
+}
+
+/// Files stop_recording can save a take as
+#[derive(Clone, Copy, PartialEq, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OutputFormat {
+    Wav,
+    Mp3,
+    Ogg,
+    Flac,
+}
+
+impl OutputFormat {
+    /// Encoder for the compressed formats; None for WAV
+    fn compressed(self) -> Option<CompressFormat> {
+        match self {
+            OutputFormat::Wav => None,
+            OutputFormat::Mp3 => Some(CompressFormat::Mp3),
+            OutputFormat::Ogg => Some(CompressFormat::Ogg),
+            OutputFormat::Flac => Some(CompressFormat::Flac),
+        }
+    }
+}
+
+/// Level check of a saved recording
+#[derive(Clone, serde::Serialize)]
+pub struct RecordingQuality {
+    pub is_silent: bool,  // peak below the silence threshold
+    pub is_clipped: bool, // too many samples at full scale
+    pub peak_db: f32,     // dBFS
+    pub rms_db: f32,      // dBFS
+}
+
 #[derive(Clone, serde::Serialize)]
 pub struct RecordingSaved {
     pub path: String,
@@ -45,36 +250,228 @@ pub struct RecordingInfo {
     pub path: String,
     pub size: u64,
     pub modified: u64,
+    pub format: String,   // lowercase extension, e.g. "wav"
+    pub duration_ms: u64, // 0 when unknown (non-WAV, encrypted or unreadable header)
+    pub encrypted: bool,  // stored as .wav.enc; play with read_encrypted_recording
+    pub title: String,
+    pub notes: String,
+    pub tags: Vec<String>,
+}
+
+/// Recordings last modified on the same local day
+#[derive(Clone, serde::Serialize)]
+pub struct RecordingGroup {
+    pub date: String,       // "YYYY-MM-DD" in local time
+    pub date_label: String, // "Today", "Yesterday" or e.g. "Monday, 12 October 2026"
+    pub recordings: Vec<RecordingInfo>,
+}
+
+/// User annotations for a recording, stored in the metadata sidecar
+#[derive(Clone, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+struct RecordingMetadata {
+    title: String,
+    notes: String,
+    tags: Vec<String>,
+}
+
+/// Input devices seen by the audio host (payload of `audio-devices-changed`)
+#[derive(Clone, serde::Serialize)]
+pub struct AudioDevices {
+    pub inputs: Vec<String>,
+    pub default_input: Option<String>,
+}
+
+/// What the audio host reports, for diagnosing recording setup problems
+/// Input capabilities are for the default input and empty when there is none.
+#[derive(Clone, serde::Serialize)]
+pub struct AudioSystemInfo {
+    pub host: String,
+    pub default_input: Option<String>,
+    pub default_output: Option<String>,
+    pub min_sample_rate: Option<u32>,
+    pub max_sample_rate: Option<u32>,
+    pub channel_counts: Vec<u16>,
+    pub sample_formats: Vec<String>, // e.g. "f32", "i16"
+}
+
+/// Per-recording settings handed to the recording thread
+struct RecordingOptions {
+    delay_ms: u64,
+    channel: Option<u16>,
+    expected_duration_ms: Option<u64>,
+    stream_to_disk: bool,
+    monitor: bool,
+    force_channels: Option<u16>,
+    loopback_device: Option<String>,
 }
 
-/// Start recording audio
+/// Start recording audio, optionally after a countdown of `delay_ms`
+/// `channel` (0-based) records only that input channel, as mono
+/// `expected_duration_ms` pre-allocates the sample buffer for known-length takes
+/// `stream_to_disk` writes the WAV while recording instead of buffering it in memory;
+/// it defaults to on for takes expected to exceed STREAM_TO_DISK_THRESHOLD_MS, and is
+/// unavailable while an encryption key is set.
+/// `monitor` plays the input back on the default output while recording; use headphones,
+/// as monitoring through speakers will feed back into the microphone.
+/// `force_channels` (1 or 2) saves that many channels regardless of the input: mono input
+/// is duplicated to stereo, and anything wider is averaged down to mono.
+/// `loopback_device` (from list_loopback_devices) records what's playing instead of the
+/// default input; it can't be combined with `monitor`.
+/// The input format is locked when recording starts. If the device renegotiates its
+/// channel count or sample rate mid-take, capture halts and `recording-config-changed` is
+/// emitted; call stop_recording to save the audio captured up to that point.
 #[tauri::command]
-pub async fn start_recording(app: AppHandle) -> Result<String, String> {
-    if RECORDING.load(Ordering::SeqCst) {
+#[allow(clippy::too_many_arguments)]
+pub async fn start_recording(
+    app: AppHandle,
+    delay_ms: Option<u64>,
+    channel: Option<u16>,
+    expected_duration_ms: Option<u64>,
+    stream_to_disk: Option<bool>,
+    monitor: Option<bool>,
+    force_channels: Option<u16>,
+    loopback_device: Option<String>,
+) -> Result<String, AppError> {
+    if RECORDING.load(Ordering::SeqCst) || COUNTDOWN_ACTIVE.load(Ordering::SeqCst) {
         return Ok("Already recording".to_string());
     }
 
+    if let Some(name) = &loopback_device {
+        if !LOOPBACK_SUPPORTED {
+            return Err(AppError::Unsupported(
+                "Loopback recording is not supported on this platform".to_string(),
+            ));
+        }
+        // Playing the output back into itself would feed back endlessly
+        if monitor == Some(true) {
+            return Err(AppError::InvalidArgument(
+                "Cannot monitor while recording a loopback device".to_string(),
+            ));
+        }
+        recording_device(Some(name)).map_err(AppError::DeviceNotFound)?;
+    }
+
+    if force_channels.is_some_and(|ch| !(1..=2).contains(&ch)) {
+        return Err(AppError::InvalidArgument(
+            "Forced channel count must be 1 or 2".to_string(),
+        ));
+    }
+
+    if let Some(channel) = channel {
+        let device_channels = recording_device(loopback_device.as_deref())
+            .and_then(|device| default_recording_config(&device, loopback_device.is_some()))
+            .map_err(AppError::DeviceNotFound)?
+            .channels();
+        if channel >= device_channels {
+            return Err(AppError::InvalidArgument(format!(
+                "Channel {} out of range: input device has {} channel(s)",
+                channel, device_channels
+            )));
+        }
+    }
+
+    // Streaming writes plain audio to disk as it goes, so it's off while encrypting
+    let encrypting = encryption_enabled();
+    if encrypting && stream_to_disk == Some(true) {
+        return Err(AppError::InvalidArgument(
+            "Cannot stream to disk while recordings are encrypted".to_string(),
+        ));
+    }
+
+    // Every check above returns before any recorder state changes
     STOP_RECORDING.store(false, Ordering::SeqCst);
 
+    let delay_ms = delay_ms.unwrap_or(0);
+    if delay_ms > 0 {
+        COUNTDOWN_ACTIVE.store(true, Ordering::SeqCst);
+    }
+
     // Clear previous samples
     {
         let mut samples = RECORDING_SAMPLES.lock();
         samples.clear();
     }
+    *STREAMED_RESULT.lock() = None;
 
+    let stream_to_disk = stream_to_disk.unwrap_or_else(|| {
+        !encrypting && expected_duration_ms.is_some_and(|ms| ms > STREAM_TO_DISK_THRESHOLD_MS)
+    });
+    let options = RecordingOptions {
+        delay_ms,
+        channel,
+        expected_duration_ms,
+        stream_to_disk,
+        monitor: monitor.unwrap_or(false),
+        force_channels,
+        loopback_device,
+    };
     let app_handle = app.clone();
     thread::spawn(move || {
-        run_recording(app_handle);
+        run_recording(app_handle, options);
     });
 
+    if delay_ms > 0 {
+        return Ok("Recording countdown started".to_string());
+    }
+
     Ok("Recording started".to_string())
 }
 
-/// Stop recording and save
+/// Stop recording and save, optionally peak-normalizing the audio first
+/// `sample_format` picks 16-bit PCM (default) or 32-bit float WAV output
+/// `formats` (e.g. ["wav", "mp3"]) saves one file per format from the same take, all
+/// sharing a base name; defaults to WAV only. Compressed formats are unavailable while
+/// an encryption key is set.
+/// Streamed recordings are already on disk as 16-bit PCM: normalizing and the sample
+/// format only apply to buffered recordings, and their WAV is always kept, with other
+/// formats encoded from it.
 #[tauri::command]
-pub async fn stop_recording(app: AppHandle) -> Result<RecordingSaved, String> {
+pub async fn stop_recording(
+    app: AppHandle,
+    normalize: Option<bool>,
+    sample_format: Option<WavSampleFormat>,
+    formats: Option<Vec<OutputFormat>>,
+) -> Result<Vec<RecordingSaved>, AppError> {
+    let mut output_formats: Vec<OutputFormat> = Vec::new();
+    for format in formats.unwrap_or_default() {
+        if !output_formats.contains(&format) {
+            output_formats.push(format);
+        }
+    }
+    if output_formats.is_empty() {
+        output_formats.push(OutputFormat::Wav);
+    }
+    // Only WAV is written encrypted; a compressed copy would leave the audio in the clear
+    if encryption_enabled() && output_formats.iter().any(|f| f.compressed().is_some()) {
+        return Err(AppError::InvalidArgument(
+            "Only WAV can be saved while recordings are encrypted".to_string(),
+        ));
+    }
+
+    // Cancel a pending countdown; nothing has been captured yet
+    if COUNTDOWN_ACTIVE.load(Ordering::SeqCst) && !RECORDING.load(Ordering::SeqCst) {
+        STOP_RECORDING.store(true, Ordering::SeqCst);
+
+        let mut attempts = 0;
+        while COUNTDOWN_ACTIVE.load(Ordering::SeqCst) && attempts < 20 {
+            thread::sleep(Duration::from_millis(50));
+            attempts += 1;
+        }
+
+        let result = RecordingSaved {
+            path: String::new(),
+            filename: String::new(),
+            duration_ms: 0,
+            success: false,
+            error: Some("Recording cancelled during countdown".to_string()),
+        };
+        let _ = app.emit("recording-saved", result.clone());
+        return Ok(vec![result]);
+    }
+
     if !RECORDING.load(Ordering::SeqCst) {
-        return Err("Not recording".to_string());
+        return Err("Not recording".into());
     }
 
     STOP_RECORDING.store(true, Ordering::SeqCst);
@@ -86,8 +483,38 @@ pub async fn stop_recording(app: AppHandle) -> Result<RecordingSaved, String> {
         attempts += 1;
     }
 
+    // A streamed recording was finalized by the recording thread
+    let streamed = STREAMED_RESULT.lock().take();
+    if let Some(result) = streamed {
+        let _ = app.emit("recording-saved", result.clone());
+        if let Some(e) = &result.error {
+            return Err(e.clone().into());
+        }
+
+        let duration_ms = result.duration_ms;
+        let wav_path = PathBuf::from(&result.path);
+        let compressed: Vec<CompressFormat> = output_formats
+            .iter()
+            .filter_map(|f| f.compressed())
+            .collect();
+        let copies = tauri::async_runtime::spawn_blocking(move || {
+            save_compressed_copies(&wav_path, &compressed)
+        })
+        .await
+        .map_err(|e| format!("Failed to save recording: {}", e))?;
+
+        let mut results = vec![result];
+        for copy in copies {
+            let saved = saved_file(copy, duration_ms);
+            let _ = app.emit("recording-saved", saved.clone());
+            results.push(saved);
+        }
+        enforce_retention(&app).await;
+        return Ok(results);
+    }
+
     // Get recorded samples
-    let samples = {
+    let mut samples = {
         let guard = RECORDING_SAMPLES.lock();
         guard.clone()
     };
@@ -103,59 +530,87 @@ pub async fn stop_recording(app: AppHandle) -> Result<RecordingSaved, String> {
             error: Some("No audio data recorded".to_string()),
         };
         let _ = app.emit("recording-saved", result.clone());
-        return Ok(result);
+        return Ok(vec![result]);
     }
 
     let duration_ms = (samples.len() as u64 * 1000) / (rate as u64 * ch as u64);
 
-    // Save as WAV
-    match save_wav(&samples, rate, ch) {
-        Ok((path, filename)) => {
-            let result = RecordingSaved {
-                path,
-                filename,
-                duration_ms,
-                success: true,
-                error: None,
-            };
-            let _ = app.emit("recording-saved", result.clone());
-            Ok(result)
-        }
-        Err(e) => {
-            let result = RecordingSaved {
-                path: String::new(),
-                filename: String::new(),
-                duration_ms,
-                success: false,
-                error: Some(e.clone()),
-            };
-            let _ = app.emit("recording-saved", result.clone());
-            Err(e)
-        }
+    if normalize.unwrap_or(false) {
+        normalize_peak(&mut samples);
+    }
+
+    // Save one file per format; a format that fails doesn't stop the others
+    let template = NAME_TEMPLATE.lock().clone();
+    let sample_format = sample_format.unwrap_or_default();
+    let saved = tauri::async_runtime::spawn_blocking(move || {
+        save_take(
+            &samples,
+            rate,
+            ch,
+            sample_format,
+            &output_formats,
+            &template,
+        )
+    })
+    .await
+    .map_err(|e| format!("Failed to save recording: {}", e))?;
+    let saved = saved.unwrap_or_else(|e| vec![Err(e)]);
+
+    let results: Vec<RecordingSaved> = saved
+        .into_iter()
+        .map(|file| saved_file(file, duration_ms))
+        .collect();
+    for result in &results {
+        let _ = app.emit("recording-saved", result.clone());
+    }
+    if !results.iter().any(|result| result.success) {
+        let error = results.iter().find_map(|result| result.error.clone());
+        return Err(error
+            .unwrap_or_else(|| "Failed to save recording".to_string())
+            .into());
     }
+    enforce_retention(&app).await;
+    Ok(results)
+}
+
+/// Stop recording (or a pending countdown) without saving anything
+#[tauri::command]
+pub async fn cancel_recording(app: AppHandle) -> Result<(), AppError> {
+    if !RECORDING.load(Ordering::SeqCst) && !COUNTDOWN_ACTIVE.load(Ordering::SeqCst) {
+        return Err("Not recording".into());
+    }
+
+    discard_recording()?;
+    let _ = app.emit("recording-cancelled", ());
+    Ok(())
 }
 
 /// List all recordings
 #[tauri::command]
-pub async fn list_recordings() -> Result<Vec<RecordingInfo>, String> {
-    let music_dir = dirs::audio_dir()
-        .or_else(|| dirs::home_dir().map(|h| h.join("Music")))
-        .ok_or("Failed to get Music directory")?;
-    let rec_dir = music_dir.join(RECORDINGS_DIR);
+pub async fn list_recordings() -> Result<Vec<RecordingInfo>, AppError> {
+    let rec_dir = recordings_dir()?;
 
     if !rec_dir.exists() {
         return Ok(Vec::new());
     }
 
     let mut recordings: Vec<RecordingInfo> = Vec::new();
+    let mut all_metadata = load_metadata(&rec_dir);
 
     let entries = fs::read_dir(&rec_dir)
-        .map_err(|e| format!("Failed to read directory: {}", e))?;
+        .map_err(|e| AppError::IoError(format!("Failed to read directory: {}", e)))?;
 
     for entry in entries.flatten() {
         let path = entry.path();
         if let Some(ext) = path.extension() {
-            if ext.to_string_lossy().to_lowercase() == "wav" {
+            // Encrypted recordings are WAVs underneath
+            let encrypted = is_encrypted_path(&path);
+            let ext_lower = if encrypted {
+                "wav".to_string()
+            } else {
+                ext.to_string_lossy().to_lowercase()
+            };
+            if AUDIO_EXTENSIONS.contains(&ext_lower.as_str()) {
                 if let Ok(metadata) = entry.metadata() {
                     let modified = metadata
                         .modified()
@@ -163,181 +618,1935 @@ pub async fn list_recordings() -> Result<Vec<RecordingInfo>, String> {
                         .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
                         .map(|d| d.as_secs())
                         .unwrap_or(0);
+                    let duration_ms = if encrypted {
+                        0
+                    } else {
+                        cached_duration_ms(&path, &ext_lower, modified, metadata.len())
+                    };
+                    let filename = entry.file_name().to_string_lossy().to_string();
+                    let annotations = all_metadata.remove(&filename).unwrap_or_default();
 
                     recordings.push(RecordingInfo {
-                        filename: entry.file_name().to_string_lossy().to_string(),
+                        filename,
                         path: path.to_string_lossy().to_string(),
                         size: metadata.len(),
                         modified,
+                        duration_ms,
+                        format: ext_lower,
+                        encrypted,
+                        title: annotations.title,
+                        notes: annotations.notes,
+                        tags: annotations.tags,
                     });
                 }
             }
         }
     }
 
-    recordings.sort_by(|a, b| b.modified.cmp(&a.modified));
+    recordings.sort_by_key(|rec| std::cmp::Reverse(rec.modified));
     Ok(recordings)
 }
 
+/// List recordings grouped by the local day they were modified, newest day first
+#[tauri::command]
+pub async fn list_recordings_grouped() -> Result<Vec<RecordingGroup>, AppError> {
+    let today = Local::now().date_naive();
+    let mut groups: Vec<RecordingGroup> = Vec::new();
+
+    // Recordings come newest first, so each day's are already together
+    for recording in list_recordings().await? {
+        let day = DateTime::from_timestamp(recording.modified as i64, 0)
+            .map(|t| t.with_timezone(&Local).date_naive())
+            .unwrap_or_default();
+        let date = day.format("%Y-%m-%d").to_string();
+
+        match groups.last_mut() {
+            Some(group) if group.date == date => group.recordings.push(recording),
+            _ => groups.push(RecordingGroup {
+                date,
+                date_label: day_label(day, today),
+                recordings: vec![recording],
+            }),
+        }
+    }
+
+    Ok(groups)
+}
+
+/// Header for a day of recordings relative to `today`
+fn day_label(day: NaiveDate, today: NaiveDate) -> String {
+    match today.signed_duration_since(day).num_days() {
+        0 => "Today".to_string(),
+        1 => "Yesterday".to_string(),
+        _ => day.format("%A, %-d %B %Y").to_string(),
+    }
+}
+
+/// Copy an external audio file (WAV, MP3, FLAC or Ogg) into the recordings directory
+/// The type is checked from the file's contents; the copy gets a unique IMPORT_ name.
+#[tauri::command]
+pub async fn import_recording(source_path: String) -> Result<RecordingInfo, AppError> {
+    let source = Path::new(&source_path);
+    if !source.is_file() {
+        return Err(AppError::IoError(format!(
+            "Source file not found: {}",
+            source_path
+        )));
+    }
+
+    // Check the actual contents, not just the extension
+    let ext = sniff_audio_format(source)?.ok_or_else(|| {
+        "Unsupported audio type: only WAV, MP3, FLAC and Ogg can be imported".to_string()
+    })?;
+    let duration_ms = match ext {
+        "wav" => read_wav_duration_ms(source)
+            .ok_or_else(|| "Unsupported WAV file: missing fmt or data chunk".to_string())?,
+        _ => 0,
+    };
+
+    let timestamp = Local::now().format("%Y%m%d_%H%M%S").to_string();
+    let rec_dir = ensure_recordings_dir()?;
+    let target = unique_path(&rec_dir, &format!("IMPORT_{}", timestamp), ext);
+
+    fs::copy(source, &target)
+        .map_err(|e| AppError::IoError(format!("Failed to import recording: {}", e)))?;
+
+    let metadata = fs::metadata(&target)
+        .map_err(|e| AppError::IoError(format!("Failed to read imported recording: {}", e)))?;
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+
+    Ok(RecordingInfo {
+        filename: target
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        path: target.to_string_lossy().to_string(),
+        size: metadata.len(),
+        modified,
+        format: ext.to_string(),
+        duration_ms,
+        encrypted: false,
+        title: String::new(),
+        notes: String::new(),
+        tags: Vec::new(),
+    })
+}
+
+/// Identify an audio file from its leading bytes, returning its extension
+fn sniff_audio_format(path: &Path) -> Result<Option<&'static str>, String> {
+    let mut header = [0u8; 12];
+    let mut file = fs::File::open(path).map_err(|e| format!("Failed to open source: {}", e))?;
+    let read = file
+        .read(&mut header)
+        .map_err(|e| format!("Failed to read source: {}", e))?;
+    let header = &header[..read];
+
+    let format = if header.len() >= 12 && &header[0..4] == b"RIFF" && &header[8..12] == b"WAVE" {
+        Some("wav")
+    } else if header.starts_with(b"fLaC") {
+        Some("flac")
+    } else if header.starts_with(b"OggS") {
+        Some("ogg")
+    } else if header.starts_with(b"ID3") || is_mp3_frame_header(header) {
+        Some("mp3")
+    } else {
+        None
+    };
+    Ok(format)
+}
+
+/// Whether `header` starts with a valid MPEG Layer III frame header
+/// The frame sync alone is just eleven set bits, so version, layer, bitrate and
+/// sample rate must hold values a real frame can have as well.
+fn is_mp3_frame_header(header: &[u8]) -> bool {
+    if header.len() < 4 || header[0] != 0xFF || header[1] & 0xE0 != 0xE0 {
+        return false;
+    }
+    let version = (header[1] >> 3) & 0b11; // 0b01 is reserved
+    let layer = (header[1] >> 1) & 0b11; // 0b01 is Layer III
+    let bitrate = header[2] >> 4; // 0 is free format, 15 is invalid
+    let sample_rate = (header[2] >> 2) & 0b11; // 0b11 is reserved
+    version != 0b01 && layer == 0b01 && bitrate != 0 && bitrate != 15 && sample_rate != 0b11
+}
+
+/// Get a recording's duration, parsing the WAV header only when the file changed
+fn cached_duration_ms(path: &Path, ext: &str, modified: u64, size: u64) -> u64 {
+    if ext != "wav" {
+        return 0;
+    }
+
+    let mut cache = DURATION_CACHE.lock();
+    if let Some(&(cached_modified, cached_size, duration)) = cache.get(path) {
+        if cached_modified == modified && cached_size == size {
+            return duration;
+        }
+    }
+
+    let duration = read_wav_duration_ms(path).unwrap_or(0);
+    cache.insert(path.to_path_buf(), (modified, size, duration));
+    duration
+}
+
 /// Read audio file as base64 data URL
 #[tauri::command]
-pub async fn read_audio_file(path: String) -> Result<String, String> {
+pub async fn read_audio_file(path: String) -> Result<String, AppError> {
     use base64::Engine;
-    let data = fs::read(&path).map_err(|e| format!("Failed to read audio: {}", e))?;
+    if is_encrypted_path(Path::new(&path)) {
+        return Err(AppError::InvalidArgument(
+            "Recording is encrypted; use read_encrypted_recording".to_string(),
+        ));
+    }
+    let data =
+        fs::read(&path).map_err(|e| AppError::IoError(format!("Failed to read audio: {}", e)))?;
     let b64 = base64::engine::general_purpose::STANDARD.encode(&data);
-    Ok(format!("data:audio/wav;base64,{}", b64))
+    let ext = std::path::Path::new(&path)
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    Ok(format!("data:{};base64,{}", audio_mime_type(&ext), b64))
 }
 
-/// Delete a recording
+/// Read `start_ms..end_ms` of a WAV recording as a small base64 WAV data URL
+/// Only that span is loaded, so long recordings can be scrubbed cheaply.
 #[tauri::command]
-pub async fn delete_recording(path: String) -> Result<bool, String> {
-    let music_dir = dirs::audio_dir()
-        .or_else(|| dirs::home_dir().map(|h| h.join("Music")))
-        .ok_or("Failed to get Music directory")?;
-    let rec_dir = music_dir.join(RECORDINGS_DIR);
-    let target = std::path::Path::new(&path);
-
-    if !target.starts_with(&rec_dir) {
-        return Err("Cannot delete files outside recordings directory".to_string());
+pub async fn read_audio_range(
+    path: String,
+    start_ms: u64,
+    end_ms: u64,
+) -> Result<String, AppError> {
+    use base64::Engine;
+    let source = Path::new(&path);
+    if !is_in_recordings_dir(source)? {
+        return Err(AppError::PathOutsideAllowedDir(
+            "Cannot read files outside recordings directory".to_string(),
+        ));
     }
 
-    fs::remove_file(&path).map_err(|e| format!("Failed to delete recording: {}", e))?;
-    Ok(true)
+    let data = read_wav_range(source, start_ms, end_ms)?;
+    let b64 = base64::engine::general_purpose::STANDARD.encode(&data);
+    Ok(format!("data:{};base64,{}", audio_mime_type("wav"), b64))
 }
 
-/// Check if currently recording
+/// MIME type for a recording's file extension
+fn audio_mime_type(ext: &str) -> &'static str {
+    match ext {
+        "mp3" => "audio/mpeg",
+        "flac" => "audio/flac",
+        "ogg" => "audio/ogg",
+        _ => "audio/wav",
+    }
+}
+
+/// Decrypt an encrypted (.wav.enc) recording and return it as a base64 WAV data URL
+/// Fails if no key is set or the key doesn't match the one it was saved with.
 #[tauri::command]
-pub async fn is_recording() -> Result<bool, String> {
-    Ok(RECORDING.load(Ordering::SeqCst))
+pub async fn read_encrypted_recording(path: String) -> Result<String, AppError> {
+    use base64::Engine;
+    let source = Path::new(&path);
+    if !is_in_recordings_dir(source)? {
+        return Err(AppError::PathOutsideAllowedDir(
+            "Cannot read files outside recordings directory".to_string(),
+        ));
+    }
+    if !is_encrypted_path(source) {
+        return Err(AppError::InvalidArgument(
+            "Recording is not encrypted".to_string(),
+        ));
+    }
+
+    let data =
+        fs::read(source).map_err(|e| AppError::IoError(format!("Failed to read audio: {}", e)))?;
+    let wav = decrypt(&data)?;
+    let b64 = base64::engine::general_purpose::STANDARD.encode(&wav);
+    Ok(format!("data:audio/wav;base64,{}", b64))
 }
 
-fn run_recording(app: AppHandle) {
-    RECORDING.store(true, Ordering::SeqCst);
+/// Check that a WAV recording is usable: not silent and not clipped
+/// Encrypted recordings are decrypted in memory with the current key.
+#[tauri::command]
+pub async fn validate_recording(path: String) -> Result<RecordingQuality, AppError> {
+    let source = Path::new(&path);
+    if !is_in_recordings_dir(source)? {
+        return Err(AppError::PathOutsideAllowedDir(
+            "Cannot read files outside recordings directory".to_string(),
+        ));
+    }
 
-    let host = cpal::default_host();
-    let device = match host.default_input_device() {
-        Some(d) => d,
-        None => {
-            let _ = app.emit("recording-error", "No input device found");
-            RECORDING.store(false, Ordering::SeqCst);
-            return;
-        }
+    let wav = if is_encrypted_path(source) {
+        let data = fs::read(source)
+            .map_err(|e| AppError::IoError(format!("Failed to read audio: {}", e)))?;
+        decode_wav(&decrypt(&data)?)?
+    } else {
+        read_wav(source)?
     };
 
-    let config = match device.default_input_config() {
-        Ok(c) => c,
-        Err(e) => {
-            let _ = app.emit("recording-error", format!("Failed to get input config: {}", e));
-            RECORDING.store(false, Ordering::SeqCst);
-            return;
-        }
-    };
+    let peak = wav.samples.iter().fold(0.0f32, |max, s| max.max(s.abs()));
+    let clipped = wav.samples.iter().filter(|s| s.abs() >= CLIP_LEVEL).count();
+    let sum_squares: f64 = wav.samples.iter().map(|&s| (s as f64) * (s as f64)).sum();
+    let samples = wav.samples.len().max(1);
+    let rms = (sum_squares / samples as f64).sqrt() as f32;
 
-    // Store config for WAV saving
-    {
-        *SAMPLE_RATE.lock() = config.sample_rate().0;
-        *CHANNELS.lock() = config.channels();
+    Ok(RecordingQuality {
+        is_silent: peak < SILENCE_PEAK,
+        is_clipped: clipped as f32 / samples as f32 > MAX_CLIPPED_FRACTION,
+        peak_db: level_db(peak),
+        rms_db: level_db(rms),
+    })
+}
+
+/// Convert a linear level (1.0 = full scale) to dBFS, no lower than LEVEL_FLOOR_DB
+fn level_db(level: f32) -> f32 {
+    (20.0 * level.log10()).max(LEVEL_FLOOR_DB)
+}
+
+/// Delete a recording
+#[tauri::command]
+pub async fn delete_recording(path: String) -> Result<bool, AppError> {
+    if !is_in_recordings_dir(Path::new(&path))? {
+        return Err(AppError::PathOutsideAllowedDir(
+            "Cannot delete files outside recordings directory".to_string(),
+        ));
     }
 
-    let samples = RECORDING_SAMPLES.clone();
-    let start_time = Instant::now();
-    let app_tick = app.clone();
+    fs::remove_file(&path)
+        .map_err(|e| AppError::IoError(format!("Failed to delete recording: {}", e)))?;
 
-    let stream = match device.build_input_stream(
-        &config.into(),
-        move |data: &[f32], _: &cpal::InputCallbackInfo| {
-            let mut guard = samples.lock();
-            guard.extend_from_slice(data);
-        },
-        move |err| {
-            eprintln!("Recording stream error: {}", err);
-        },
-        None,
-    ) {
-        Ok(s) => s,
-        Err(e) => {
-            let _ = app.emit("recording-error", format!("Failed to build stream: {}", e));
-            RECORDING.store(false, Ordering::SeqCst);
-            return;
+    // The recording is gone; leftover cache or sidecar entries are only logged
+    let target = Path::new(&path);
+    if let Err(e) = remove_cached_thumbnails(target) {
+        eprintln!("Failed to clean up thumbnails for {}: {}", path, e);
+    }
+
+    // Drop its metadata so a later file with the same name doesn't inherit it
+    if let (Some(dir), Some(filename)) = (target.parent(), target.file_name()) {
+        let mut all_metadata = load_metadata(dir);
+        if all_metadata.remove(&*filename.to_string_lossy()).is_some() {
+            if let Err(e) = save_metadata(dir, &all_metadata) {
+                eprintln!("Failed to clean up metadata for {}: {}", path, e);
+            }
         }
-    };
+    }
 
-    if let Err(e) = stream.play() {
-        let _ = app.emit("recording-error", format!("Failed to start stream: {}", e));
-        RECORDING.store(false, Ordering::SeqCst);
-        return;
+    Ok(true)
+}
+
+/// Set a recording's title, notes and tags (empty values clear them)
+#[tauri::command]
+pub async fn set_recording_metadata(
+    path: String,
+    title: String,
+    notes: String,
+    tags: Vec<String>,
+) -> Result<(), AppError> {
+    let target = Path::new(&path);
+    if !is_in_recordings_dir(target)? || !target.is_file() {
+        return Err(AppError::InvalidArgument(format!(
+            "Not a recording: {}",
+            path
+        )));
     }
+    let filename = target
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .ok_or("Recording has no file name")?;
+    let dir = target.parent().ok_or("Recording has no parent directory")?;
 
-    // Send duration updates
-    loop {
-        if STOP_RECORDING.load(Ordering::SeqCst) {
-            break;
+    // Trimmed, non-empty, without duplicates, in the order given
+    let mut clean_tags: Vec<String> = Vec::new();
+    for tag in tags.iter().map(|t| t.trim()).filter(|t| !t.is_empty()) {
+        if !clean_tags.iter().any(|t| t == tag) {
+            clean_tags.push(tag.to_string());
         }
-        let elapsed = start_time.elapsed().as_millis() as u64;
-        let _ = app_tick.emit("recording-status", RecordingStatus {
-            recording: true,
-            duration_ms: elapsed,
-        });
-        thread::sleep(Duration::from_millis(200));
     }
 
-    drop(stream);
-    RECORDING.store(false, Ordering::SeqCst);
-    STOP_RECORDING.store(false, Ordering::SeqCst);
+    let entry = RecordingMetadata {
+        title: title.trim().to_string(),
+        notes: notes.trim().to_string(),
+        tags: clean_tags,
+    };
+
+    let mut all_metadata = load_metadata(dir);
+    if entry.title.is_empty() && entry.notes.is_empty() && entry.tags.is_empty() {
+        all_metadata.remove(&filename);
+    } else {
+        all_metadata.insert(filename, entry);
+    }
+    save_metadata(dir, &all_metadata)?;
+    Ok(())
 }
 
-fn save_wav(samples: &[f32], sample_rate: u32, channels: u16) -> Result<(String, String), String> {
-    let music_dir = dirs::audio_dir()
-        .or_else(|| dirs::home_dir().map(|h| h.join("Music")))
-        .ok_or("Failed to get Music directory")?;
-    let rec_dir = music_dir.join(RECORDINGS_DIR);
+/// Cut a recording down to `start_ms..end_ms` and save it as a new file
+#[tauri::command]
+pub async fn trim_recording(
+    app: AppHandle,
+    path: String,
+    start_ms: u64,
+    end_ms: u64,
+) -> Result<RecordingSaved, AppError> {
+    let source = Path::new(&path);
+    if !is_in_recordings_dir(source)? {
+        return Err(AppError::PathOutsideAllowedDir(
+            "Cannot trim files outside recordings directory".to_string(),
+        ));
+    }
+
+    let wav = read_wav(source)?;
+    let duration_ms = wav.duration_ms();
 
-    if !rec_dir.exists() {
-        fs::create_dir_all(&rec_dir)
-            .map_err(|e| format!("Failed to create recordings directory: {}", e))?;
+    if start_ms >= end_ms || end_ms > duration_ms {
+        return Err(AppError::InvalidArgument(format!(
+            "Invalid trim range {}..{} ms for a {} ms recording",
+            start_ms, end_ms, duration_ms
+        )));
     }
 
-    let timestamp = Local::now().format("%Y%m%d_%H%M%S").to_string();
-    let filename = format!("REC_{}.wav", timestamp);
-    let filepath = rec_dir.join(&filename);
-
-    // Write WAV manually
-    let num_samples = samples.len() as u32;
-    let bits_per_sample: u16 = 16;
-    let byte_rate = sample_rate * channels as u32 * (bits_per_sample as u32 / 8);
-    let block_align = channels * (bits_per_sample / 8);
-    let data_size = num_samples * (bits_per_sample as u32 / 8);
-
-    let mut buf = Cursor::new(Vec::new());
-
-    // RIFF header
-    buf.write_all(b"RIFF").map_err(|e| e.to_string())?;
-    buf.write_all(&(36 + data_size).to_le_bytes()).map_err(|e| e.to_string())?;
-    buf.write_all(b"WAVE").map_err(|e| e.to_string())?;
-
-    // fmt chunk
-    buf.write_all(b"fmt ").map_err(|e| e.to_string())?;
-    buf.write_all(&16u32.to_le_bytes()).map_err(|e| e.to_string())?;
-    buf.write_all(&1u16.to_le_bytes()).map_err(|e| e.to_string())?; // PCM
-    buf.write_all(&channels.to_le_bytes()).map_err(|e| e.to_string())?;
-    buf.write_all(&sample_rate.to_le_bytes()).map_err(|e| e.to_string())?;
-    buf.write_all(&byte_rate.to_le_bytes()).map_err(|e| e.to_string())?;
-    buf.write_all(&block_align.to_le_bytes()).map_err(|e| e.to_string())?;
-    buf.write_all(&bits_per_sample.to_le_bytes()).map_err(|e| e.to_string())?;
-
-    // data chunk
-    buf.write_all(b"data").map_err(|e| e.to_string())?;
-    buf.write_all(&data_size.to_le_bytes()).map_err(|e| e.to_string())?;
-
-    // Convert f32 samples to i16
-    for &sample in samples {
-        let clamped = sample.clamp(-1.0, 1.0);
-        let val = (clamped * 32767.0) as i16;
-        buf.write_all(&val.to_le_bytes()).map_err(|e| e.to_string())?;
-    }
-
-    fs::write(&filepath, buf.into_inner())
-        .map_err(|e| format!("Failed to write WAV file: {}", e))?;
-
-    Ok((filepath.to_string_lossy().to_string(), filename))
+    let start = ms_to_samples(start_ms, wav.sample_rate, wav.channels);
+    let end = ms_to_samples(end_ms, wav.sample_rate, wav.channels).min(wav.samples.len());
+    let trimmed = &wav.samples[start..end];
+
+    let stem = source
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| "REC".to_string());
+    let filepath = unique_path(&ensure_recordings_dir()?, &format!("{}_trim", stem), "wav");
+
+    write_wav_file(
+        &filepath,
+        trimmed,
+        wav.sample_rate,
+        wav.channels,
+        wav.sample_format,
+    )?;
+
+    let result = RecordingSaved {
+        path: filepath.to_string_lossy().to_string(),
+        filename: filepath
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        duration_ms: samples_to_ms(trimmed.len(), wav.sample_rate, wav.channels),
+        success: true,
+        error: None,
+    };
+    let _ = app.emit("recording-saved", result.clone());
+    Ok(result)
+}
+
+/// Join recordings end to end into a new file named `output_name`
+/// All inputs must share a sample rate and channel count. `output_name` may use the
+/// same placeholders as the recording filename template.
+#[tauri::command]
+pub async fn concat_recordings(
+    app: AppHandle,
+    paths: Vec<String>,
+    output_name: String,
+) -> Result<RecordingSaved, AppError> {
+    if paths.len() < 2 {
+        return Err(AppError::InvalidArgument(
+            "Select at least two recordings to join".to_string(),
+        ));
+    }
+
+    let output_name = output_name.trim();
+    let output_name = output_name.strip_suffix(".wav").unwrap_or(output_name);
+    validate_name_template(output_name)?;
+
+    // Check every input before reading any audio
+    for path in &paths {
+        if !is_in_recordings_dir(Path::new(path))? {
+            return Err(AppError::PathOutsideAllowedDir(format!(
+                "Cannot read files outside recordings directory: {}",
+                path
+            )));
+        }
+    }
+
+    let first = read_wav(Path::new(&paths[0]))
+        .map_err(|e| format!("Failed to read {}: {}", paths[0], e))?;
+    let (sample_rate, channels, sample_format) =
+        (first.sample_rate, first.channels, first.sample_format);
+    let mut samples = first.samples;
+
+    for path in &paths[1..] {
+        let wav =
+            read_wav(Path::new(path)).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+        if wav.sample_rate != sample_rate || wav.channels != channels {
+            return Err(AppError::InvalidArgument(format!(
+                "{} is {} Hz / {} channel(s), expected {} Hz / {} channel(s)",
+                path, wav.sample_rate, wav.channels, sample_rate, channels
+            )));
+        }
+        samples.extend_from_slice(&wav.samples);
+    }
+
+    let stem = render_name_template(output_name, Local::now(), 1, 1);
+    let filepath = unique_path(&ensure_recordings_dir()?, &stem, "wav");
+
+    write_wav_file(&filepath, &samples, sample_rate, channels, sample_format)?;
+
+    let result = RecordingSaved {
+        path: filepath.to_string_lossy().to_string(),
+        filename: filepath
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        duration_ms: samples_to_ms(samples.len(), sample_rate, channels),
+        success: true,
+        error: None,
+    };
+    let _ = app.emit("recording-saved", result.clone());
+    Ok(result)
+}
+
+/// Convert a recording to `target_rate` Hz (and optionally mono), saving it as a new file
+/// Useful for pipelines that expect e.g. 16 kHz mono; works for up- and downsampling.
+#[tauri::command]
+pub async fn resample_recording(
+    app: AppHandle,
+    path: String,
+    target_rate: u32,
+    mono: bool,
+) -> Result<RecordingSaved, AppError> {
+    if !(MIN_RESAMPLE_RATE..=MAX_RESAMPLE_RATE).contains(&target_rate) {
+        return Err(AppError::InvalidArgument(format!(
+            "Target rate must be between {} and {} Hz",
+            MIN_RESAMPLE_RATE, MAX_RESAMPLE_RATE
+        )));
+    }
+
+    let source = Path::new(&path);
+    if !is_in_recordings_dir(source)? {
+        return Err(AppError::PathOutsideAllowedDir(
+            "Cannot resample files outside recordings directory".to_string(),
+        ));
+    }
+
+    let wav = read_wav(source)?;
+
+    // Downmix first so the resampler has less to do
+    let (samples, channels) = if mono && wav.channels > 1 {
+        let mut mixed = Vec::with_capacity(wav.samples.len() / wav.channels as usize);
+        for frame in wav.samples.chunks_exact(wav.channels as usize) {
+            remix_frame(frame, 1, |s| mixed.push(s));
+        }
+        (mixed, 1)
+    } else {
+        (wav.samples, wav.channels)
+    };
+
+    let resampled = tauri::async_runtime::spawn_blocking(move || {
+        resample(&samples, channels, wav.sample_rate, target_rate)
+    })
+    .await
+    .map_err(|e| format!("Failed to resample recording: {}", e))??;
+
+    let stem = source
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| "REC".to_string());
+    let suffix = if mono { "_mono" } else { "" };
+    let name = format!("{}_{}hz{}", stem, target_rate, suffix);
+    let filepath = unique_path(&ensure_recordings_dir()?, &name, "wav");
+
+    write_wav_file(
+        &filepath,
+        &resampled,
+        target_rate,
+        channels,
+        wav.sample_format,
+    )?;
+
+    let result = RecordingSaved {
+        path: filepath.to_string_lossy().to_string(),
+        filename: filepath
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        duration_ms: samples_to_ms(resampled.len(), target_rate, channels),
+        success: true,
+        error: None,
+    };
+    let _ = app.emit("recording-saved", result.clone());
+    Ok(result)
+}
+
+/// Transcode a WAV recording to MP3 (128 kbps), Ogg Vorbis or FLAC, saved as a new file
+/// Emits `recording-compress-progress` while encoding. With `delete_original` the WAV is
+/// deleted afterwards and its title, notes and tags move to the new file.
+#[tauri::command]
+pub async fn compress_recording(
+    app: AppHandle,
+    path: String,
+    format: CompressFormat,
+    delete_original: Option<bool>,
+) -> Result<RecordingSaved, AppError> {
+    let source = Path::new(&path);
+    if !is_in_recordings_dir(source)? {
+        return Err(AppError::PathOutsideAllowedDir(
+            "Cannot compress files outside recordings directory".to_string(),
+        ));
+    }
+    if is_encrypted_path(source) {
+        return Err(AppError::InvalidArgument(
+            "Encrypted recordings can't be compressed".to_string(),
+        ));
+    }
+
+    let wav = read_wav(source)?;
+    let duration_ms = wav.duration_ms();
+
+    let progress_app = app.clone();
+    let progress_path = path.clone();
+    let encoded = tauri::async_runtime::spawn_blocking(move || {
+        // Report whole percents only, so long files don't flood the frontend
+        let mut last_percent = None;
+        let progress = |fraction: f32| {
+            let percent = (fraction * 100.0) as u32;
+            if last_percent != Some(percent) {
+                last_percent = Some(percent);
+                let _ = progress_app.emit(
+                    "recording-compress-progress",
+                    CompressProgress {
+                        path: progress_path.clone(),
+                        progress: fraction,
+                    },
+                );
+            }
+        };
+
+        encode_compressed(
+            format,
+            &wav.samples,
+            wav.sample_rate,
+            wav.channels,
+            wav.sample_format,
+            progress,
+        )
+    })
+    .await
+    .map_err(|e| format!("Failed to compress recording: {}", e))??;
+
+    let rec_dir = ensure_recordings_dir()?;
+    let stem = source
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| "REC".to_string());
+    let filepath = unique_path(&rec_dir, &stem, format.extension());
+    let filename = filepath
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    fs::write(&filepath, &encoded)
+        .map_err(|e| AppError::IoError(format!("Failed to write compressed recording: {}", e)))?;
+
+    if delete_original.unwrap_or(false) {
+        // Move the annotations across before the original's entry is dropped
+        let mut all_metadata = load_metadata(&rec_dir);
+        let original = source.file_name().map(|n| n.to_string_lossy().to_string());
+        if let Some(annotations) = original.and_then(|name| all_metadata.remove(&name)) {
+            all_metadata.insert(filename.clone(), annotations);
+            save_metadata(&rec_dir, &all_metadata)?;
+        }
+        delete_recording(path).await?;
+    }
+
+    let result = RecordingSaved {
+        path: filepath.to_string_lossy().to_string(),
+        filename,
+        duration_ms,
+        success: true,
+        error: None,
+    };
+    let _ = app.emit("recording-saved", result.clone());
+    Ok(result)
+}
+
+/// Resample interleaved audio from `from_rate` to `to_rate` with an FFT resampler
+fn resample(
+    samples: &[f32],
+    channels: u16,
+    from_rate: u32,
+    to_rate: u32,
+) -> Result<Vec<f32>, String> {
+    if from_rate == to_rate {
+        return Ok(samples.to_vec());
+    }
+
+    let channels = channels as usize;
+    let frames = samples.len() / channels;
+    let planar: Vec<Vec<f32>> = (0..channels)
+        .map(|c| samples.iter().skip(c).step_by(channels).copied().collect())
+        .collect();
+
+    let mut resampler = FftFixedIn::<f32>::new(
+        from_rate as usize,
+        to_rate as usize,
+        RESAMPLE_CHUNK_FRAMES,
+        2,
+        channels,
+    )
+    .map_err(|e| format!("Failed to create resampler: {}", e))?;
+
+    // The resampler delays its output; keep feeding (zeros at the end) until the
+    // delayed tail has come out too
+    let delay = resampler.output_delay();
+    let expected = (frames as u64 * to_rate as u64).div_ceil(from_rate as u64) as usize;
+    let mut output: Vec<Vec<f32>> = vec![Vec::with_capacity(expected + delay); channels];
+    let mut position = 0;
+    while output[0].len() < expected + delay {
+        let needed = resampler.input_frames_next();
+        let input: Vec<&[f32]> = planar
+            .iter()
+            .map(|ch| &ch[position.min(frames)..(position + needed).min(frames)])
+            .collect();
+        let chunk = if position + needed <= frames {
+            resampler.process(&input, None)
+        } else if position < frames {
+            resampler.process_partial(Some(&input), None)
+        } else {
+            resampler.process_partial(None::<&[&[f32]]>, None)
+        }
+        .map_err(|e| format!("Failed to resample audio: {}", e))?;
+        position += needed;
+
+        for (out, resampled) in output.iter_mut().zip(chunk) {
+            out.extend(resampled);
+        }
+    }
+
+    // Drop the delay and interleave again
+    let mut interleaved = Vec::with_capacity(expected * channels);
+    for i in delay..delay + expected {
+        interleaved.extend(output.iter().map(|ch| ch[i]));
+    }
+    Ok(interleaved)
+}
+
+/// Rewrite the header of a recording whose sizes don't match its data (e.g. after a crash)
+#[tauri::command]
+pub async fn repair_wav(app: AppHandle, path: String) -> Result<RecordingSaved, AppError> {
+    let filepath = Path::new(&path);
+    if !is_in_recordings_dir(filepath)? {
+        return Err(AppError::PathOutsideAllowedDir(
+            "Cannot repair files outside recordings directory".to_string(),
+        ));
+    }
+
+    repair_wav_header(filepath)?;
+
+    let result = RecordingSaved {
+        path: path.clone(),
+        filename: filepath
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        duration_ms: read_wav_duration_ms(filepath).unwrap_or(0),
+        success: true,
+        error: None,
+    };
+    let _ = app.emit("recording-saved", result.clone());
+    Ok(result)
+}
+
+/// Set the software input gain applied while recording (1.0 = unchanged)
+#[tauri::command]
+pub async fn set_recording_gain(gain: f32) -> Result<(), AppError> {
+    if !gain.is_finite() || !(0.0..=MAX_RECORDING_GAIN).contains(&gain) {
+        return Err(AppError::InvalidArgument(format!(
+            "Gain must be between 0.0 and {}",
+            MAX_RECORDING_GAIN
+        )));
+    }
+
+    *RECORDING_GAIN.lock() = gain;
+    Ok(())
+}
+
+/// Get the current software input gain
+#[tauri::command]
+pub async fn get_recording_gain() -> Result<f32, AppError> {
+    Ok(*RECORDING_GAIN.lock())
+}
+
+/// Sample formats the default input supports that recordings can be captured in
+#[tauri::command]
+pub async fn list_input_sample_formats() -> Result<Vec<InputSampleFormat>, AppError> {
+    let device = cpal::default_host()
+        .default_input_device()
+        .ok_or_else(|| AppError::DeviceNotFound("No input device found".to_string()))?;
+    let supported: Vec<cpal::SampleFormat> = device
+        .supported_input_configs()
+        .map_err(|e| format!("Failed to query input configs: {}", e))?
+        .map(|config| config.sample_format())
+        .collect();
+
+    Ok(InputSampleFormat::ALL
+        .into_iter()
+        .filter(|format| supported.contains(&format.to_cpal()))
+        .collect())
+}
+
+/// Capture new recordings in `format`, or pass null to follow the device default
+/// Takes effect from the next start_recording.
+#[tauri::command]
+pub async fn set_input_sample_format(format: Option<InputSampleFormat>) -> Result<(), AppError> {
+    if let Some(format) = format {
+        if !list_input_sample_formats().await?.contains(&format) {
+            return Err(AppError::InvalidArgument(format!(
+                "Input device doesn't support the {} sample format",
+                format.to_cpal()
+            )));
+        }
+    }
+
+    *INPUT_SAMPLE_FORMAT.lock() = format;
+    Ok(())
+}
+
+/// Names of the devices that record what the kiosk is playing, for start_recording
+/// On Windows these are the output devices; on Linux, ALSA inputs named like a loopback:
+/// the snd-aloop card, or a PulseAudio/PipeWire monitor source the system has exposed as
+/// an ALSA PCM with "monitor" in its name (cpal doesn't list monitor sources by
+/// itself). macOS has no native loopback and returns an `unsupported` error; a virtual
+/// device such as BlackHole can be selected as the default input instead.
+#[tauri::command]
+pub async fn list_loopback_devices() -> Result<Vec<String>, AppError> {
+    if !LOOPBACK_SUPPORTED {
+        return Err(AppError::Unsupported(
+            "Loopback recording is not supported on this platform".to_string(),
+        ));
+    }
+
+    let names = loopback_devices(&cpal::default_host())?
+        .iter()
+        .filter_map(|device| device.name().ok())
+        .collect();
+    Ok(names)
+}
+
+/// Re-enumerate audio input devices (e.g. after a USB mic was swapped)
+/// Recordings resolve the default device when they start, so the next
+/// start_recording picks up whatever the host reports here.
+#[tauri::command]
+pub async fn refresh_audio_devices(app: AppHandle) -> Result<AudioDevices, AppError> {
+    let host = cpal::default_host();
+
+    let inputs = host
+        .input_devices()
+        .map_err(|e| format!("Failed to list input devices: {}", e))?
+        .filter_map(|device| device.name().ok())
+        .collect();
+    let default_input = host.default_input_device().and_then(|d| d.name().ok());
+
+    let devices = AudioDevices {
+        inputs,
+        default_input,
+    };
+    let _ = app.emit("audio-devices-changed", devices.clone());
+    Ok(devices)
+}
+
+/// Describe the audio host, default devices and the default input's supported configs
+#[tauri::command]
+pub async fn audio_system_info() -> Result<AudioSystemInfo, AppError> {
+    let host = cpal::default_host();
+    let input = host.default_input_device();
+    let output = host.default_output_device();
+
+    let mut info = AudioSystemInfo {
+        host: host.id().name().to_string(),
+        default_input: input.as_ref().and_then(|d| d.name().ok()),
+        default_output: output.as_ref().and_then(|d| d.name().ok()),
+        min_sample_rate: None,
+        max_sample_rate: None,
+        channel_counts: Vec::new(),
+        sample_formats: Vec::new(),
+    };
+
+    if let Some(device) = input {
+        let configs = device
+            .supported_input_configs()
+            .map_err(|e| format!("Failed to query input configs: {}", e))?;
+
+        for config in configs {
+            let (min, max) = (config.min_sample_rate().0, config.max_sample_rate().0);
+            info.min_sample_rate = Some(info.min_sample_rate.map_or(min, |r| r.min(min)));
+            info.max_sample_rate = Some(info.max_sample_rate.map_or(max, |r| r.max(max)));
+
+            if !info.channel_counts.contains(&config.channels()) {
+                info.channel_counts.push(config.channels());
+            }
+            let format = config.sample_format().to_string();
+            if !info.sample_formats.contains(&format) {
+                info.sample_formats.push(format);
+            }
+        }
+        info.channel_counts.sort_unstable();
+    }
+
+    Ok(info)
+}
+
+/// Set the filename template for new recordings (e.g. "Lobby_{date}") and persist it
+/// See render_name_template for the placeholders; "REC_{session}" numbers recordings
+/// REC_001, REC_002, ... until reset_recording_index.
+#[tauri::command]
+pub async fn set_recording_name_template(template: String) -> Result<(), AppError> {
+    let template = template.trim().to_string();
+    validate_name_template(&template)?;
+
+    let path = name_template_path().ok_or("Failed to get home directory")?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| AppError::IoError(format!("Failed to create config directory: {}", e)))?;
+    }
+    fs::write(&path, &template)
+        .map_err(|e| AppError::IoError(format!("Failed to save filename template: {}", e)))?;
+
+    *NAME_TEMPLATE.lock() = template;
+    Ok(())
+}
+
+/// Restart {session} numbering at 001, e.g. at the start of an event
+#[tauri::command]
+pub async fn reset_recording_index() -> Result<(), AppError> {
+    let mut session = SESSION_INDEX.lock();
+    save_session_index(0).map_err(AppError::IoError)?;
+    *session = 0;
+    Ok(())
+}
+
+/// Get the filename template used for new recordings
+#[tauri::command]
+pub async fn get_recording_name_template() -> Result<String, AppError> {
+    Ok(NAME_TEMPLATE.lock().clone())
+}
+
+/// Check if currently recording
+#[tauri::command]
+pub async fn is_recording() -> Result<bool, AppError> {
+    Ok(RECORDING.load(Ordering::SeqCst))
+}
+
+/// Emit a `recording-countdown` event each second; returns false if cancelled
+fn run_countdown(app: &AppHandle, delay_ms: u64) -> bool {
+    let delay = Duration::from_millis(delay_ms);
+    let start_time = Instant::now();
+    let mut last_emitted = None;
+
+    while start_time.elapsed() < delay {
+        if STOP_RECORDING.load(Ordering::SeqCst) {
+            return false;
+        }
+
+        let remaining_ms = delay.saturating_sub(start_time.elapsed()).as_millis() as u64;
+        let seconds_left = remaining_ms.div_ceil(1000);
+        if last_emitted != Some(seconds_left) {
+            let _ = app.emit("recording-countdown", RecordingCountdown {
+                seconds_left,
+                remaining_ms,
+            });
+            last_emitted = Some(seconds_left);
+        }
+
+        thread::sleep(Duration::from_millis(50));
+    }
+
+    !STOP_RECORDING.load(Ordering::SeqCst)
+}
+
+/// Device to record from: the loopback device named `loopback`, or the default input
+fn recording_device(loopback: Option<&str>) -> Result<cpal::Device, String> {
+    let host = cpal::default_host();
+    match loopback {
+        Some(name) => loopback_devices(&host)?
+            .into_iter()
+            .find(|device| device.name().is_ok_and(|n| n == name))
+            .ok_or_else(|| format!("Loopback device not found: {}", name)),
+        None => host
+            .default_input_device()
+            .ok_or_else(|| "No input device found".to_string()),
+    }
+}
+
+/// Devices that capture the audio being played (see LOOPBACK_SUPPORTED)
+fn loopback_devices(host: &cpal::Host) -> Result<Vec<cpal::Device>, String> {
+    if cfg!(target_os = "windows") {
+        return host
+            .output_devices()
+            .map(|devices| devices.collect())
+            .map_err(|e| format!("Failed to list output devices: {}", e));
+    }
+
+    let devices = host
+        .input_devices()
+        .map_err(|e| format!("Failed to list input devices: {}", e))?
+        .filter(|device| {
+            device.name().is_ok_and(|name| {
+                let name = name.to_lowercase();
+                LOOPBACK_NAME_HINTS.iter().any(|hint| name.contains(hint))
+            })
+        })
+        .collect();
+    Ok(devices)
+}
+
+/// Default config of the device being recorded; WASAPI loopback devices are outputs
+fn default_recording_config(
+    device: &cpal::Device,
+    loopback: bool,
+) -> Result<cpal::SupportedStreamConfig, String> {
+    if loopback && cfg!(target_os = "windows") {
+        device
+            .default_output_config()
+            .map_err(|e| format!("Failed to get output config: {}", e))
+    } else {
+        device
+            .default_input_config()
+            .map_err(|e| format!("Failed to get input config: {}", e))
+    }
+}
+
+/// Input config to record with: the device default, switched to `preferred` (or to the
+/// first supported format when the default isn't one we can convert) at the same
+/// channel count and sample rate
+pub(crate) fn choose_input_config(
+    device: &cpal::Device,
+    preferred: Option<InputSampleFormat>,
+) -> Result<cpal::SupportedStreamConfig, String> {
+    let default = device
+        .default_input_config()
+        .map_err(|e| format!("Failed to get input config: {}", e))?;
+    let default_format = InputSampleFormat::from_cpal(default.sample_format());
+    let wanted = match (preferred, default_format) {
+        (Some(format), _) if Some(format) != default_format => vec![format],
+        (None, None) => InputSampleFormat::ALL.to_vec(),
+        _ => return Ok(default),
+    };
+
+    let (channels, rate) = (default.channels(), default.sample_rate());
+    let configs: Vec<cpal::SupportedStreamConfigRange> = device
+        .supported_input_configs()
+        .map_err(|e| format!("Failed to query input configs: {}", e))?
+        .filter(|c| {
+            c.channels() == channels && c.min_sample_rate() <= rate && rate <= c.max_sample_rate()
+        })
+        .collect();
+    wanted
+        .iter()
+        .find_map(|format| {
+            configs
+                .iter()
+                .find(|c| c.sample_format() == format.to_cpal())
+                .copied()
+                .map(|c| c.with_sample_rate(rate))
+        })
+        .ok_or_else(|| match preferred {
+            Some(format) => format!(
+                "Input device doesn't support the {} sample format at {} Hz",
+                format.to_cpal(),
+                rate.0
+            ),
+            None => format!(
+                "Unsupported input sample format: {}",
+                default.sample_format()
+            ),
+        })
+}
+
+/// Build an input stream in the config's sample format, handing `on_data` f32 samples
+pub(crate) fn build_f32_input_stream(
+    device: &cpal::Device,
+    config: &cpal::SupportedStreamConfig,
+    mut on_data: impl FnMut(&[f32]) + Send + 'static,
+) -> Result<cpal::Stream, String> {
+    let stream_config = config.config();
+    let on_error = |err| eprintln!("Input stream error: {}", err);
+    let stream = match config.sample_format() {
+        cpal::SampleFormat::F32 => device.build_input_stream(
+            &stream_config,
+            move |data: &[f32], _: &cpal::InputCallbackInfo| on_data(data),
+            on_error,
+            None,
+        ),
+        cpal::SampleFormat::I16 => {
+            device.build_input_stream(&stream_config, converted::<i16>(on_data), on_error, None)
+        }
+        cpal::SampleFormat::U16 => {
+            device.build_input_stream(&stream_config, converted::<u16>(on_data), on_error, None)
+        }
+        other => return Err(format!("Unsupported input sample format: {}", other)),
+    };
+    stream.map_err(|e| format!("Failed to build stream: {}", e))
+}
+
+/// Input callback converting `T` samples to f32, reusing one buffer between calls
+fn converted<T>(
+    mut on_data: impl FnMut(&[f32]) + Send + 'static,
+) -> impl FnMut(&[T], &cpal::InputCallbackInfo) + Send + 'static
+where
+    T: cpal::Sample + 'static,
+    f32: cpal::FromSample<T>,
+{
+    let mut buffer = Vec::new();
+    move |data: &[T], _: &cpal::InputCallbackInfo| {
+        buffer.clear();
+        buffer.extend(data.iter().map(|&s| s.to_sample::<f32>()));
+        on_data(&buffer);
+    }
+}
+
+fn run_recording(app: AppHandle, options: RecordingOptions) {
+    if options.delay_ms > 0 && !run_countdown(&app, options.delay_ms) {
+        COUNTDOWN_ACTIVE.store(false, Ordering::SeqCst);
+        STOP_RECORDING.store(false, Ordering::SeqCst);
+        return;
+    }
+
+    // Only flip to recording once the countdown is over
+    RECORDING.store(true, Ordering::SeqCst);
+    COUNTDOWN_ACTIVE.store(false, Ordering::SeqCst);
+
+    let device = match recording_device(options.loopback_device.as_deref()) {
+        Ok(d) => d,
+        Err(e) => {
+            let _ = app.emit("recording-error", e);
+            RECORDING.store(false, Ordering::SeqCst);
+            return;
+        }
+    };
+
+    // WASAPI loopback captures in the output's mix format, so no sample format is chosen
+    let loopback = options.loopback_device.is_some();
+    let config = if loopback && cfg!(target_os = "windows") {
+        default_recording_config(&device, loopback)
+    } else {
+        choose_input_config(&device, *INPUT_SAMPLE_FORMAT.lock())
+    };
+    let config = match config {
+        Ok(c) => c,
+        Err(e) => {
+            let _ = app.emit("recording-error", e);
+            RECORDING.store(false, Ordering::SeqCst);
+            return;
+        }
+    };
+
+    // A selected channel must exist on the device (it may have changed since start_recording)
+    let device_channels = config.channels();
+    if let Some(channel) = options.channel {
+        if channel >= device_channels {
+            let _ = app.emit(
+                "recording-error",
+                format!(
+                    "Channel {} out of range: input device has {} channel(s)",
+                    channel, device_channels
+                ),
+            );
+            RECORDING.store(false, Ordering::SeqCst);
+            return;
+        }
+    }
+
+    // Store config for WAV saving
+    {
+        *SAMPLE_RATE.lock() = config.sample_rate().0;
+        let source_channels = if options.channel.is_some() {
+            1
+        } else {
+            device_channels
+        };
+        *CHANNELS.lock() = options.force_channels.unwrap_or(source_channels);
+    }
+
+    // Open the output file up front when streaming to disk
+    let mut writer = if options.stream_to_disk {
+        let rate = *SAMPLE_RATE.lock();
+        let ch = *CHANNELS.lock();
+        let template = NAME_TEMPLATE.lock().clone();
+        let created = next_recording_path(&template, "wav").and_then(|path| {
+            WavWriter::create(&path, rate, ch, WavSampleFormat::Pcm16).map(|w| (w, path))
+        });
+        match created {
+            Ok(writer) => Some(writer),
+            Err(e) => {
+                let _ = app.emit("recording-error", e);
+                RECORDING.store(false, Ordering::SeqCst);
+                return;
+            }
+        }
+    } else {
+        None
+    };
+    STREAMING_TO_DISK.store(writer.is_some(), Ordering::SeqCst);
+
+    // Reserve the buffer up front so the input callback doesn't reallocate mid-take
+    if let (Some(expected_ms), None) = (options.expected_duration_ms, &writer) {
+        let rate = *SAMPLE_RATE.lock();
+        let ch = *CHANNELS.lock();
+        let capacity = ms_to_samples(expected_ms.min(MAX_PREALLOC_DURATION_MS), rate, ch);
+        let mut guard = RECORDING_SAMPLES.lock();
+        let additional = capacity.saturating_sub(guard.len());
+        guard.reserve(additional);
+    }
+
+    let samples = RECORDING_SAMPLES.clone();
+    let start_time = Instant::now();
+    let app_tick = app.clone();
+    let sample_rate = *SAMPLE_RATE.lock();
+
+    // Set once the input no longer matches the locked format; the callback then drops data
+    let input_halted = Arc::new(AtomicBool::new(false));
+    let input_halted_callback = input_halted.clone();
+
+    // Mono copy of the input for the headphone monitor, capped to keep latency low
+    let monitor_buffer = options
+        .monitor
+        .then(|| Arc::new(Mutex::new(VecDeque::<f32>::new())));
+    let monitor_input = monitor_buffer.clone();
+    let monitor_capacity = ms_to_samples(MONITOR_MAX_LATENCY_MS, *SAMPLE_RATE.lock(), 1);
+
+    // Per-frame peaks collected between status updates for the live waveform
+    let frame_peaks = Arc::new(Mutex::new(Vec::<f32>::new()));
+    let frame_peaks_input = frame_peaks.clone();
+
+    let on_input = move |data: &[f32]| {
+        // A buffer that isn't whole frames means the channel count changed under us
+        if input_halted_callback.load(Ordering::SeqCst)
+            || !data.len().is_multiple_of(device_channels as usize)
+        {
+            input_halted_callback.store(true, Ordering::SeqCst);
+            return;
+        }
+
+        // Apply gain before buffering so everything downstream sees the boosted signal
+        let gain = *RECORDING_GAIN.lock();
+        let apply_gain = |s: f32| (s * gain).clamp(-1.0, 1.0);
+        let mut guard = samples.lock();
+        match (options.channel, options.force_channels) {
+            // Convert each frame (or the selected channel) to the forced layout
+            (channel, Some(out_channels)) => {
+                for frame in data.chunks_exact(device_channels as usize) {
+                    let source = match channel {
+                        Some(c) => &frame[c as usize..c as usize + 1],
+                        None => frame,
+                    };
+                    remix_frame(source, out_channels, |s| guard.push(apply_gain(s)));
+                }
+            }
+            // Keep only the selected channel from the interleaved input
+            (Some(channel), None) => guard.extend(
+                data.iter()
+                    .skip(channel as usize)
+                    .step_by(device_channels as usize)
+                    .map(|&s| apply_gain(s)),
+            ),
+            (None, None) if gain == 1.0 => guard.extend_from_slice(data),
+            (None, None) => guard.extend(data.iter().map(|&s| apply_gain(s))),
+        }
+        drop(guard);
+
+        let peaks = data.chunks(device_channels as usize).map(|frame| {
+            let peak = match options.channel {
+                Some(channel) => frame.get(channel as usize).map_or(0.0, |s| s.abs()),
+                None => frame.iter().fold(0.0f32, |max, s| max.max(s.abs())),
+            };
+            (peak * gain).min(1.0)
+        });
+        frame_peaks_input.lock().extend(peaks);
+
+        if let Some(monitor) = &monitor_input {
+            let mut queue = monitor.lock();
+            for frame in data.chunks(device_channels as usize) {
+                let sample = match options.channel {
+                    Some(channel) => frame.get(channel as usize).copied().unwrap_or(0.0),
+                    None => frame.iter().sum::<f32>() / frame.len() as f32,
+                };
+                queue.push_back(apply_gain(sample));
+            }
+            let excess = queue.len().saturating_sub(monitor_capacity);
+            queue.drain(..excess);
+        }
+    };
+
+    // Devices that don't offer f32 are captured natively and converted in the callback
+    let sample_format = InputSampleFormat::from_cpal(config.sample_format());
+    let stream = match build_f32_input_stream(&device, &config, on_input) {
+        Ok(s) => s,
+        Err(e) => {
+            let _ = app.emit("recording-error", e);
+            RECORDING.store(false, Ordering::SeqCst);
+            return;
+        }
+    };
+
+    if let Err(e) = stream.play() {
+        let _ = app.emit("recording-error", format!("Failed to start stream: {}", e));
+        STREAMING_TO_DISK.store(false, Ordering::SeqCst);
+        RECORDING.store(false, Ordering::SeqCst);
+        return;
+    }
+    if let Some(sample_format) = sample_format {
+        let _ = app.emit("recording-format", RecordingFormat {
+            sample_format,
+            sample_rate,
+            channels: device_channels,
+        });
+    }
+
+    // Monitoring is a convenience; recording carries on without it
+    let monitor_stream = monitor_buffer.and_then(|buffer| {
+        build_monitor_stream(buffer, *SAMPLE_RATE.lock())
+            .map_err(|e| {
+                let _ = app.emit("monitor-error", e);
+            })
+            .ok()
+    });
+
+    // Send duration updates (and flush samples to disk when streaming)
+    let mut pending: Vec<f32> = Vec::new();
+    let mut write_error: Option<String> = None;
+    let mut config_changed = false;
+    let mut last_config_check = Instant::now();
+    loop {
+        if STOP_RECORDING.load(Ordering::SeqCst) {
+            break;
+        }
+        if let Some((w, _)) = writer.as_mut() {
+            if let Err(e) = flush_to_writer(w, &mut pending) {
+                let _ = app.emit("recording-error", e.clone());
+                write_error = Some(e);
+                break;
+            }
+        }
+
+        // Halt capture (but keep the take) if the device renegotiated its format
+        if !config_changed
+            && (input_halted.load(Ordering::SeqCst)
+                || last_config_check.elapsed() >= CONFIG_CHECK_INTERVAL)
+        {
+            last_config_check = Instant::now();
+            // Querying may fail while our stream holds the device; that alone isn't a change
+            let current = default_recording_config(&device, loopback).ok();
+            let mismatched = current.as_ref().is_some_and(|c| {
+                c.channels() != device_channels || c.sample_rate().0 != sample_rate
+            });
+            if mismatched || input_halted.load(Ordering::SeqCst) {
+                input_halted.store(true, Ordering::SeqCst);
+                config_changed = true;
+                let _ = app.emit(
+                    "recording-config-changed",
+                    RecordingConfigChanged {
+                        expected_channels: device_channels,
+                        expected_sample_rate: sample_rate,
+                        channels: current.as_ref().map(|c| c.channels()),
+                        sample_rate: current.as_ref().map(|c| c.sample_rate().0),
+                        duration_ms: start_time.elapsed().as_millis() as u64,
+                    },
+                );
+            }
+        }
+        if config_changed {
+            thread::sleep(Duration::from_millis(200));
+            continue;
+        }
+
+        let elapsed = start_time.elapsed().as_millis() as u64;
+        let _ = app_tick.emit("recording-status", RecordingStatus {
+            recording: true,
+            duration_ms: elapsed,
+        });
+        let peaks = decimate_peaks(&std::mem::take(&mut *frame_peaks.lock()), WAVEFORM_POINTS);
+        if !peaks.is_empty() {
+            let _ = app_tick.emit("recording-waveform", RecordingWaveform { peaks });
+        }
+        thread::sleep(Duration::from_millis(200));
+    }
+
+    drop(monitor_stream);
+    drop(stream);
+
+    // Write what's left and patch the header
+    if let Some((mut w, path)) = writer {
+        let finished = match write_error {
+            Some(e) => Err(e),
+            None => flush_to_writer(&mut w, &mut pending).and_then(|_| w.finish()),
+        };
+        *STREAMED_RESULT.lock() = Some(streamed_result(&path, finished));
+        STREAMING_TO_DISK.store(false, Ordering::SeqCst);
+    }
+
+    RECORDING.store(false, Ordering::SeqCst);
+    STOP_RECORDING.store(false, Ordering::SeqCst);
+}
+
+/// Emit one frame as `out_channels` channels: mono averages the source, stereo
+/// duplicates a mono source or keeps the first two channels
+fn remix_frame(frame: &[f32], out_channels: u16, mut push: impl FnMut(f32)) {
+    if out_channels == 1 {
+        push(frame.iter().sum::<f32>() / frame.len() as f32);
+    } else {
+        let left = frame[0];
+        push(left);
+        push(frame.get(1).copied().unwrap_or(left));
+    }
+}
+
+/// Reduce per-frame peaks to at most `points` values, keeping the loudest in each bucket
+fn decimate_peaks(frame_peaks: &[f32], points: usize) -> Vec<f32> {
+    if frame_peaks.is_empty() {
+        return Vec::new();
+    }
+    let bucket = frame_peaks.len().div_ceil(points);
+    frame_peaks
+        .chunks(bucket)
+        .map(|chunk| chunk.iter().fold(0.0f32, |max, &p| max.max(p)))
+        .collect()
+}
+
+/// Play the monitor buffer on every channel of the default output device
+fn build_monitor_stream(
+    buffer: Arc<Mutex<VecDeque<f32>>>,
+    sample_rate: u32,
+) -> Result<cpal::Stream, String> {
+    let device = cpal::default_host()
+        .default_output_device()
+        .ok_or("No output device found for monitoring")?;
+    let output_config = device
+        .default_output_config()
+        .map_err(|e| format!("Failed to get output config: {}", e))?;
+    let channels = output_config.channels();
+
+    // Run at the input rate so samples pass straight through
+    let config = cpal::StreamConfig {
+        channels,
+        sample_rate: cpal::SampleRate(sample_rate),
+        buffer_size: cpal::BufferSize::Default,
+    };
+
+    let stream = device
+        .build_output_stream(
+            &config,
+            move |data: &mut [f32], _: &cpal::OutputCallbackInfo| {
+                let mut queue = buffer.lock();
+                for frame in data.chunks_mut(channels as usize) {
+                    // Silence while the input catches up
+                    frame.fill(queue.pop_front().unwrap_or(0.0));
+                }
+            },
+            move |err| {
+                eprintln!("Monitor stream error: {}", err);
+            },
+            None,
+        )
+        .map_err(|e| format!("Failed to build monitor stream: {}", e))?;
+
+    stream
+        .play()
+        .map_err(|e| format!("Failed to start monitor stream: {}", e))?;
+    Ok(stream)
+}
+
+/// Move buffered samples into the WAV writer, reusing `pending` as scratch space
+fn flush_to_writer(writer: &mut WavWriter, pending: &mut Vec<f32>) -> Result<(), String> {
+    // Swap buffers so the input callback isn't blocked while we write
+    std::mem::swap(&mut *RECORDING_SAMPLES.lock(), pending);
+    let result = writer.write_samples(pending);
+    pending.clear();
+    result
+}
+
+/// Build the saved-recording result for a streamed file
+fn streamed_result(path: &Path, finished: Result<u64, String>) -> RecordingSaved {
+    let (duration_ms, error) = match finished {
+        Ok(duration_ms) => (duration_ms, None),
+        Err(e) => (0, Some(e)),
+    };
+    RecordingSaved {
+        path: path.to_string_lossy().to_string(),
+        filename: path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        duration_ms,
+        success: error.is_none(),
+        error,
+    }
+}
+
+/// Scale samples so the loudest one hits NORMALIZE_TARGET_PEAK
+/// Silent or already near-clipping audio is left untouched.
+fn normalize_peak(samples: &mut [f32]) {
+    let peak = samples.iter().fold(0.0f32, |max, s| max.max(s.abs()));
+
+    if !(SILENCE_PEAK..NORMALIZE_TARGET_PEAK).contains(&peak) {
+        return;
+    }
+
+    let gain = NORMALIZE_TARGET_PEAK / peak;
+    for sample in samples.iter_mut() {
+        *sample *= gain;
+    }
+}
+
+/// Stop any countdown or recording and throw the audio away
+/// A file already streamed to disk is deleted as well.
+pub(crate) fn discard_recording() -> Result<(), String> {
+    if RECORDING.load(Ordering::SeqCst) || COUNTDOWN_ACTIVE.load(Ordering::SeqCst) {
+        STOP_RECORDING.store(true, Ordering::SeqCst);
+
+        // Wait for the recording thread to finish (with timeout)
+        let mut attempts = 0;
+        while (RECORDING.load(Ordering::SeqCst) || COUNTDOWN_ACTIVE.load(Ordering::SeqCst))
+            && attempts < 100
+        {
+            thread::sleep(Duration::from_millis(50));
+            attempts += 1;
+        }
+
+        if RECORDING.load(Ordering::SeqCst) || COUNTDOWN_ACTIVE.load(Ordering::SeqCst) {
+            return Err("Recording failed to stop in time".to_string());
+        }
+    }
+
+    STOP_RECORDING.store(false, Ordering::SeqCst);
+    RECORDING_SAMPLES.lock().clear();
+    if let Some(streamed) = STREAMED_RESULT.lock().take() {
+        if !streamed.path.is_empty() {
+            if let Err(e) = fs::remove_file(&streamed.path) {
+                eprintln!(
+                    "Failed to delete discarded recording {}: {}",
+                    streamed.path, e
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Flush an in-progress recording to disk before the app exits
+pub fn recover_recording_on_exit() {
+    if !RECORDING.load(Ordering::SeqCst) {
+        return;
+    }
+
+    STOP_RECORDING.store(true, Ordering::SeqCst);
+
+    // A streamed recording is already on disk; give the thread a moment to finalize it
+    if STREAMING_TO_DISK.load(Ordering::SeqCst) {
+        let mut attempts = 0;
+        while RECORDING.load(Ordering::SeqCst) && attempts < 20 {
+            thread::sleep(Duration::from_millis(50));
+            attempts += 1;
+        }
+        if let Some(result) = STREAMED_RESULT.lock().take() {
+            println!("Saved streamed recording to {}", result.path);
+        }
+        return;
+    }
+
+    let samples = {
+        let guard = RECORDING_SAMPLES.lock();
+        guard.clone()
+    };
+    if samples.is_empty() {
+        return;
+    }
+
+    let rate = *SAMPLE_RATE.lock();
+    let ch = *CHANNELS.lock();
+
+    let saved = save_take(
+        &samples,
+        rate,
+        ch,
+        WavSampleFormat::default(),
+        &[OutputFormat::Wav],
+        RECOVERED_NAME_TEMPLATE,
+    );
+    match saved.and_then(|mut paths| paths.remove(0)) {
+        Ok(path) => println!("Recovered in-progress recording to {}", path.display()),
+        Err(e) => eprintln!("Failed to recover recording on exit: {}", e),
+    }
+}
+
+/// Save a take once per format, every file sharing a base name
+/// Returns each format's path, or why that format couldn't be written.
+fn save_take(
+    samples: &[f32],
+    sample_rate: u32,
+    channels: u16,
+    sample_format: WavSampleFormat,
+    formats: &[OutputFormat],
+    name_template: &str,
+) -> Result<Vec<Result<PathBuf, String>>, String> {
+    // With a key set the WAV is only ever written encrypted
+    let encrypting = encryption_enabled();
+    let exts: Vec<&str> = formats
+        .iter()
+        .map(|format| match format.compressed() {
+            Some(compressed) => compressed.extension(),
+            None if encrypting => ENCRYPTED_EXTENSION,
+            None => "wav",
+        })
+        .collect();
+    let paths = next_recording_paths(name_template, &exts)?;
+
+    let saved = formats
+        .iter()
+        .zip(paths)
+        .map(|(format, filepath)| {
+            let written = match format.compressed() {
+                Some(compressed) => encode_compressed(
+                    compressed,
+                    samples,
+                    sample_rate,
+                    channels,
+                    sample_format,
+                    |_| {},
+                )
+                .and_then(|bytes| {
+                    fs::write(&filepath, bytes)
+                        .map_err(|e| format!("Failed to write recording: {}", e))
+                }),
+                None if encrypting => encode_wav(samples, sample_rate, channels, sample_format)
+                    .and_then(|bytes| encrypt(&bytes))
+                    .and_then(|bytes| {
+                        fs::write(&filepath, bytes)
+                            .map_err(|e| format!("Failed to write WAV file: {}", e))
+                    }),
+                None => write_wav_file(&filepath, samples, sample_rate, channels, sample_format),
+            };
+            written.map(|_| filepath)
+        })
+        .collect();
+    Ok(saved)
+}
+
+/// Encode a saved WAV in each compressed format, next to it under the same base name
+fn save_compressed_copies(
+    wav_path: &Path,
+    formats: &[CompressFormat],
+) -> Vec<Result<PathBuf, String>> {
+    if formats.is_empty() {
+        return Vec::new();
+    }
+    let wav = match read_wav(wav_path) {
+        Ok(wav) => wav,
+        Err(e) => return formats.iter().map(|_| Err(e.clone())).collect(),
+    };
+
+    let dir = wav_path.parent().unwrap_or(Path::new("."));
+    let stem = wav_path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| "REC".to_string());
+    formats
+        .iter()
+        .map(|&format| {
+            let filepath = unique_path(dir, &stem, format.extension());
+            let bytes = encode_compressed(
+                format,
+                &wav.samples,
+                wav.sample_rate,
+                wav.channels,
+                wav.sample_format,
+                |_| {},
+            )?;
+            fs::write(&filepath, bytes).map_err(|e| format!("Failed to write recording: {}", e))?;
+            Ok(filepath)
+        })
+        .collect()
+}
+
+/// Encode samples with the encoder for `format`
+fn encode_compressed(
+    format: CompressFormat,
+    samples: &[f32],
+    sample_rate: u32,
+    channels: u16,
+    source_format: WavSampleFormat,
+    progress: impl FnMut(f32),
+) -> Result<Vec<u8>, String> {
+    match format {
+        CompressFormat::Mp3 => encode_mp3(samples, sample_rate, channels, progress),
+        CompressFormat::Ogg => encode_ogg(samples, sample_rate, channels, progress),
+        CompressFormat::Flac => {
+            // FLAC is lossless; float recordings keep 24 bits
+            let bits = match source_format {
+                WavSampleFormat::Pcm16 => 16,
+                WavSampleFormat::Float32 => 24,
+            };
+            encode_flac(samples, sample_rate, channels, bits, progress)
+        }
+    }
+}
+
+/// Describe one file written by stop_recording, or why it couldn't be
+fn saved_file(saved: Result<PathBuf, String>, duration_ms: u64) -> RecordingSaved {
+    match saved {
+        Ok(path) => RecordingSaved {
+            path: path.to_string_lossy().to_string(),
+            filename: path
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            duration_ms,
+            success: true,
+            error: None,
+        },
+        Err(e) => RecordingSaved {
+            path: String::new(),
+            filename: String::new(),
+            duration_ms,
+            success: false,
+            error: Some(e),
+        },
+    }
+}
+
+/// Pick the path for a new recording from a filename template
+fn next_recording_path(name_template: &str, ext: &str) -> Result<PathBuf, String> {
+    let mut paths = next_recording_paths(name_template, &[ext])?;
+    Ok(paths.remove(0))
+}
+
+/// Pick one path per extension for a new recording, all sharing a base name that is
+/// free for every extension
+fn next_recording_paths(name_template: &str, exts: &[&str]) -> Result<Vec<PathBuf>, String> {
+    let rec_dir = ensure_recordings_dir()?;
+    let now = Local::now();
+    let mut last_session = SESSION_INDEX.lock();
+    let next_session = *last_session + 1;
+    let paths_for = |stem: String| -> Vec<PathBuf> {
+        exts.iter()
+            .map(|ext| rec_dir.join(format!("{}.{}", stem, ext)))
+            .collect()
+    };
+
+    // {session} takes the next session number, skipping numbers already on disk; {index}
+    // counts up to the first free name; otherwise collisions get _1, _2, ...
+    type Stems<'a> = Box<dyn Iterator<Item = (String, Option<u32>)> + 'a>;
+    let stems: Stems = if name_template.contains("{session}") {
+        Box::new((next_session..).map(move |session| {
+            let stem = render_name_template(name_template, now, 1, session);
+            (stem, Some(session))
+        }))
+    } else if name_template.contains("{index}") {
+        Box::new((1..).map(move |index| {
+            let stem = render_name_template(name_template, now, index, next_session);
+            (stem, None)
+        }))
+    } else {
+        let stem = render_name_template(name_template, now, 1, next_session);
+        let numbered = (1..).map({
+            let stem = stem.clone();
+            move |index| (format!("{}_{}", stem, index), None)
+        });
+        Box::new(std::iter::once((stem, None)).chain(numbered))
+    };
+    let (paths, session) = stems
+        .map(|(stem, session)| (paths_for(stem), session))
+        .find(|(paths, _)| paths.iter().all(|path| !path.exists()))
+        .ok_or_else(|| "Failed to find a free recording filename".to_string())?;
+
+    // The number is taken even if the save later fails; a missed save of the counter
+    // only risks reusing a number whose files are then skipped
+    if let Some(session) = session {
+        *last_session = session;
+        if let Err(e) = save_session_index(session) {
+            eprintln!("Failed to save recording index: {}", e);
+        }
+    }
+    Ok(paths)
+}
+
+/// Fill in a filename template
+/// {date} = 2024-01-01, {time} = 12-00-00, {timestamp} = 20240101_120000, {index} = 1, 2, ...
+/// {session} = 001, 002, ... (counts across recordings until reset_recording_index)
+fn render_name_template(template: &str, now: DateTime<Local>, index: u32, session: u32) -> String {
+    template
+        .replace("{date}", &now.format("%Y-%m-%d").to_string())
+        .replace("{time}", &now.format("%H-%M-%S").to_string())
+        .replace("{timestamp}", &now.format("%Y%m%d_%H%M%S").to_string())
+        .replace("{index}", &index.to_string())
+        .replace("{session}", &format!("{:03}", session))
+}
+
+/// Check that a template always renders to a safe, plain filename
+fn validate_name_template(template: &str) -> Result<(), String> {
+    let rendered = render_name_template(template, Local::now(), 1, 1);
+
+    if rendered.trim().is_empty() {
+        return Err("Filename template must not be empty".to_string());
+    }
+    if rendered.contains('{') || rendered.contains('}') {
+        return Err(format!(
+            "Unknown placeholder in filename template: {} \
+             (use {{date}}, {{time}}, {{timestamp}}, {{index}} or {{session}})",
+            template
+        ));
+    }
+    if rendered.starts_with('.') {
+        return Err("Filename template must not start with '.'".to_string());
+    }
+    if let Some(c) = rendered
+        .chars()
+        .find(|&c| c.is_control() || "/\\:*?\"<>|".contains(c))
+    {
+        return Err(format!(
+            "Filename template contains invalid character {:?}",
+            c
+        ));
+    }
+    if rendered.len() > MAX_FILENAME_LEN {
+        return Err(format!(
+            "Filename template is too long (max {} characters)",
+            MAX_FILENAME_LEN
+        ));
+    }
+
+    Ok(())
+}
+
+/// Path of the persisted filename template (~/.config/honeybee/recording-name-template)
+fn name_template_path() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".config/honeybee/recording-name-template"))
+}
+
+/// Load the saved filename template, falling back to the default if missing or invalid
+fn load_name_template() -> String {
+    name_template_path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .map(|template| template.trim().to_string())
+        .filter(|template| validate_name_template(template).is_ok())
+        .unwrap_or_else(|| DEFAULT_NAME_TEMPLATE.to_string())
+}
+
+/// Path of the persisted session counter (~/.config/honeybee/recording-session-index)
+fn session_index_path() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".config/honeybee/recording-session-index"))
+}
+
+/// Load the last session number used; a missing or unreadable file starts from scratch
+fn load_session_index() -> u32 {
+    session_index_path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|index| index.trim().parse().ok())
+        .unwrap_or(0)
+}
+
+/// Persist the last session number used
+fn save_session_index(index: u32) -> Result<(), String> {
+    let path = session_index_path().ok_or("Failed to get home directory")?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create config directory: {}", e))?;
+    }
+    fs::write(&path, index.to_string())
+        .map_err(|e| format!("Failed to save recording index: {}", e))
+}
+
+/// Encode samples as WAV and write them to `filepath`
+fn write_wav_file(
+    filepath: &Path,
+    samples: &[f32],
+    sample_rate: u32,
+    channels: u16,
+    sample_format: WavSampleFormat,
+) -> Result<(), String> {
+    let bytes = encode_wav(samples, sample_rate, channels, sample_format)?;
+    fs::write(filepath, bytes).map_err(|e| format!("Failed to write WAV file: {}", e))
+}
+
+/// Read the metadata sidecar; a missing or unreadable file means no metadata
+fn load_metadata(rec_dir: &Path) -> HashMap<String, RecordingMetadata> {
+    fs::read(rec_dir.join(METADATA_FILE))
+        .ok()
+        .and_then(|data| serde_json::from_slice(&data).ok())
+        .unwrap_or_default()
+}
+
+/// Write the metadata sidecar
+fn save_metadata(
+    rec_dir: &Path,
+    all_metadata: &HashMap<String, RecordingMetadata>,
+) -> Result<(), String> {
+    let data = serde_json::to_vec_pretty(all_metadata)
+        .map_err(|e| format!("Failed to encode recording metadata: {}", e))?;
+    fs::write(rec_dir.join(METADATA_FILE), data)
+        .map_err(|e| format!("Failed to save recording metadata: {}", e))
+}
+
+/// Get the recordings directory (~/Music/honeybee-recordings)
+pub(crate) fn recordings_dir() -> Result<PathBuf, String> {
+    Ok(music_base_dir()?.join(RECORDINGS_DIR))
+}
+
+/// Get the recordings directory, creating it if needed
+fn ensure_recordings_dir() -> Result<PathBuf, String> {
+    let rec_dir = recordings_dir()?;
+
+    if !rec_dir.exists() {
+        fs::create_dir_all(&rec_dir)
+            .map_err(|e| format!("Failed to create recordings directory: {}", e))?;
+    }
+
+    Ok(rec_dir)
+}
+
+/// Check that a path points inside the recordings directory
+pub(crate) fn is_in_recordings_dir(path: &Path) -> Result<bool, String> {
+    let rec_dir = recordings_dir()?;
+    let escapes = path.components().any(|c| c == Component::ParentDir);
+    Ok(path.starts_with(&rec_dir) && !escapes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_peak_scales_to_target() {
+        let mut samples = vec![0.25, -0.5, 0.1];
+        normalize_peak(&mut samples);
+
+        let gain = NORMALIZE_TARGET_PEAK / 0.5;
+        assert!((samples[0] - 0.25 * gain).abs() < 1e-6);
+        assert!((samples[1] + NORMALIZE_TARGET_PEAK).abs() < 1e-6);
+        assert!((samples[2] - 0.1 * gain).abs() < 1e-6);
+    }
+
+    #[test]
+    fn normalize_peak_leaves_silence_alone() {
+        let original = vec![SILENCE_PEAK / 2.0, -SILENCE_PEAK / 4.0, 0.0];
+        let mut samples = original.clone();
+        normalize_peak(&mut samples);
+        assert_eq!(samples, original);
+    }
+
+    #[test]
+    fn normalize_peak_leaves_target_peak_alone() {
+        let original = vec![NORMALIZE_TARGET_PEAK, -0.3, 0.2];
+        let mut samples = original.clone();
+        normalize_peak(&mut samples);
+        assert_eq!(samples, original);
+    }
+
+    #[test]
+    fn mp3_frame_header_needs_valid_fields() {
+        // MPEG-1 Layer III, 128 kbit/s, 44.1 kHz
+        assert!(is_mp3_frame_header(&[0xFF, 0xFB, 0x90, 0x64]));
+        // Frame sync followed by a reserved version, Layer I, or a bad bitrate
+        assert!(!is_mp3_frame_header(&[0xFF, 0xEB, 0x90, 0x64]));
+        assert!(!is_mp3_frame_header(&[0xFF, 0xFF, 0x90, 0x64]));
+        assert!(!is_mp3_frame_header(&[0xFF, 0xFB, 0xF0, 0x64]));
+        assert!(!is_mp3_frame_header(&[0xFF, 0xFB]));
+    }
 }
@@ -0,0 +1,191 @@
+//! Automatic cleanup so an unattended kiosk never fills its disk
+//!
+//! The policy is opt-in: with every limit unset nothing is ever deleted. Photos and
+//! recordings are limited separately, each against the same limits, and the oldest
+//! files go first. The newest file of each kind is always kept, so a capture that was
+//! just reported as saved never disappears straight away.
+
+use crate::error::AppError;
+use crate::gallery::{delete_gallery_image, list_gallery_images};
+use crate::recorder::{delete_recording, list_recordings};
+use parking_lot::Mutex;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::{AppHandle, Emitter};
+
+static ENFORCING_RETENTION: AtomicBool = AtomicBool::new(false);
+
+lazy_static::lazy_static! {
+    // Active policy, persisted across restarts
+    static ref RETENTION_POLICY: Mutex<RetentionPolicy> = Mutex::new(load_policy());
+}
+
+/// Limits applied to the photos and the recordings, each on their own
+#[derive(Clone, Copy, Default, serde::Serialize, serde::Deserialize)]
+pub struct RetentionPolicy {
+    pub max_files: Option<usize>,
+    pub max_total_bytes: Option<u64>,
+    pub max_age_days: Option<u32>,
+}
+
+impl RetentionPolicy {
+    fn is_enabled(&self) -> bool {
+        self.max_files.is_some() || self.max_total_bytes.is_some() || self.max_age_days.is_some()
+    }
+
+    /// A zero limit would delete everything but the newest file, so it's never accepted
+    fn is_valid(&self) -> bool {
+        self.max_files != Some(0) && self.max_total_bytes != Some(0) && self.max_age_days != Some(0)
+    }
+}
+
+/// Files removed by one cleanup pass
+#[derive(Clone, serde::Serialize)]
+pub struct RetentionCleanup {
+    pub deleted: Vec<String>,
+}
+
+/// Photo or recording considered for cleanup
+struct MediaFile {
+    path: String,
+    size: u64,
+    modified: u64, // unix timestamp
+    recording: bool,
+}
+
+/// Set and persist the retention policy, then apply it right away
+/// Pass null for every limit to turn cleanup off.
+#[tauri::command]
+pub async fn set_retention_policy(
+    app: AppHandle,
+    max_files: Option<usize>,
+    max_total_bytes: Option<u64>,
+    max_age_days: Option<u32>,
+) -> Result<(), AppError> {
+    let policy = RetentionPolicy {
+        max_files,
+        max_total_bytes,
+        max_age_days,
+    };
+    if !policy.is_valid() {
+        return Err(AppError::InvalidArgument(
+            "Retention limits must be greater than zero".to_string(),
+        ));
+    }
+
+    let path = policy_path().ok_or("Failed to get home directory")?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| AppError::IoError(format!("Failed to create config directory: {}", e)))?;
+    }
+    let data = serde_json::to_vec_pretty(&policy)
+        .map_err(|e| format!("Failed to encode retention policy: {}", e))?;
+    fs::write(&path, data)
+        .map_err(|e| AppError::IoError(format!("Failed to save retention policy: {}", e)))?;
+
+    *RETENTION_POLICY.lock() = policy;
+    enforce_retention(&app).await;
+    Ok(())
+}
+
+/// Get the current retention policy
+#[tauri::command]
+pub async fn get_retention_policy() -> Result<RetentionPolicy, AppError> {
+    Ok(*RETENTION_POLICY.lock())
+}
+
+/// Delete the oldest photos and recordings beyond the policy limits
+/// Emits `retention-cleanup` when anything was removed. Failures are only logged,
+/// since this runs after a save that already succeeded.
+pub(crate) async fn enforce_retention(app: &AppHandle) {
+    let policy = *RETENTION_POLICY.lock();
+    if !policy.is_enabled() || ENFORCING_RETENTION.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    let mut deleted = Vec::new();
+    match list_gallery_images(None).await {
+        Ok(images) => {
+            let files = images.into_iter().map(|image| MediaFile {
+                path: image.path,
+                size: image.size,
+                modified: image.modified,
+                recording: false,
+            });
+            deleted.extend(delete_expired(&policy, files.collect()).await);
+        }
+        Err(e) => eprintln!("Retention: failed to list photos: {}", e),
+    }
+    match list_recordings().await {
+        Ok(recordings) => {
+            let files = recordings.into_iter().map(|recording| MediaFile {
+                path: recording.path,
+                size: recording.size,
+                modified: recording.modified,
+                recording: true,
+            });
+            deleted.extend(delete_expired(&policy, files.collect()).await);
+        }
+        Err(e) => eprintln!("Retention: failed to list recordings: {}", e),
+    }
+
+    ENFORCING_RETENTION.store(false, Ordering::SeqCst);
+    if !deleted.is_empty() {
+        let _ = app.emit("retention-cleanup", RetentionCleanup { deleted });
+    }
+}
+
+/// Delete the files of one kind that fall outside the policy, returning their paths
+async fn delete_expired(policy: &RetentionPolicy, mut files: Vec<MediaFile>) -> Vec<String> {
+    let cutoff = policy.max_age_days.map(|days| {
+        let now = chrono::Utc::now().timestamp().max(0) as u64;
+        now.saturating_sub(u64::from(days) * 24 * 60 * 60)
+    });
+
+    // Walk newest first, keeping files while they fit within the limits; the first file
+    // that doesn't fit is deleted along with everything older, so the oldest go first
+    files.sort_by_key(|file| std::cmp::Reverse(file.modified));
+    let (mut kept_files, mut kept_bytes) = (0usize, 0u64);
+    let mut over_limit = false;
+    let mut deleted = Vec::new();
+    for (i, file) in files.into_iter().enumerate() {
+        over_limit = over_limit
+            || (i > 0
+                && (cutoff.is_some_and(|cutoff| file.modified < cutoff)
+                    || policy.max_files.is_some_and(|max| kept_files >= max)
+                    || policy
+                        .max_total_bytes
+                        .is_some_and(|max| kept_bytes + file.size > max)));
+        if !over_limit {
+            kept_files += 1;
+            kept_bytes += file.size;
+            continue;
+        }
+
+        let result = if file.recording {
+            delete_recording(file.path.clone()).await
+        } else {
+            delete_gallery_image(file.path.clone()).await
+        };
+        match result {
+            Ok(_) => deleted.push(file.path),
+            Err(e) => eprintln!("Retention: failed to delete {}: {}", file.path, e),
+        }
+    }
+    deleted
+}
+
+/// Path of the persisted policy (~/.config/honeybee/retention.json)
+fn policy_path() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".config/honeybee/retention.json"))
+}
+
+/// Load the saved policy; a missing, unreadable or invalid file means no limits
+fn load_policy() -> RetentionPolicy {
+    policy_path()
+        .and_then(|path| fs::read(path).ok())
+        .and_then(|data| serde_json::from_slice::<RetentionPolicy>(&data).ok())
+        .filter(RetentionPolicy::is_valid)
+        .unwrap_or_default()
+}
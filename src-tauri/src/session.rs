@@ -0,0 +1,45 @@
+//! Session teardown for the "next user" flow
+//!
+//! Stops every background device worker the app can start and drops the
+//! transient state it holds, so the next session starts from a clean slate.
+
+use crate::camera::{
+    cancel_capture, clear_frame_ring, clear_last_capture, stop_camera_stream,
+    stop_frame_ringbuffer, stop_png_sequence, stop_timelapse,
+};
+use crate::error::AppError;
+use crate::gallery::stop_gallery_watch;
+use crate::multi_camera::release_camera_multi;
+use crate::recorder::discard_recording;
+use crate::sound_trigger::stop_sound_triggered_capture;
+
+/// Release the camera, stop any recording and watchers, and clear transient state
+/// Safe to call repeatedly; anything that isn't running is skipped.
+#[tauri::command]
+pub async fn reset_all_devices() -> Result<(), AppError> {
+    // Keep going if one step fails so the rest is still released
+    let results = [
+        stop_sound_triggered_capture().await.map(|_| ()),
+        cancel_capture().await.map(|_| ()),
+        stop_timelapse().await.map(|_| ()),
+        stop_png_sequence().await.map(|_| ()),
+        stop_frame_ringbuffer().await.map(|_| ()),
+        release_camera_multi().await.map(|_| ()),
+        stop_camera_stream().await.map(|_| ()),
+        discard_recording().map_err(AppError::from),
+        stop_gallery_watch().await.map(|_| ()),
+    ];
+
+    clear_last_capture();
+    clear_frame_ring();
+
+    let errors: Vec<String> = results
+        .into_iter()
+        .filter_map(|result| result.err().map(String::from))
+        .collect();
+    if !errors.is_empty() {
+        return Err(format!("Failed to reset devices: {}", errors.join("; ")).into());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,175 @@
+//! Sound-triggered photo capture
+//!
+//! Listens to the default input and saves a photo from the running camera stream
+//! whenever the input peak crosses a threshold, at most once per cooldown.
+
+use crate::camera::{capture_photo, is_camera_running};
+use crate::error::AppError;
+use crate::recorder::{build_f32_input_stream, choose_input_config, is_recording};
+use cpal::traits::{HostTrait, StreamTrait};
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicU32, Ordering},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+use tauri::{AppHandle, Emitter};
+
+// How often the input level is checked
+const LEVEL_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+static SOUND_TRIGGER_RUNNING: AtomicBool = AtomicBool::new(false);
+static STOP_SOUND_TRIGGER: AtomicBool = AtomicBool::new(false);
+
+/// Sound-triggered capture event payload
+#[derive(Clone, serde::Serialize)]
+pub struct SoundTriggeredCapture {
+    pub path: String,
+    pub level: f32, // input peak that triggered the capture, 0.0-1.0
+}
+
+/// Start saving a photo whenever the input peak reaches `threshold` (0.0-1.0)
+/// Needs a running camera stream and a free microphone; emits `sound-triggered-capture`.
+#[tauri::command]
+pub async fn start_sound_triggered_capture(
+    app: AppHandle,
+    threshold: f32,
+    cooldown_ms: u64,
+) -> Result<String, AppError> {
+    if SOUND_TRIGGER_RUNNING.load(Ordering::SeqCst) {
+        return Ok("Sound-triggered capture already running".to_string());
+    }
+
+    if !threshold.is_finite() || !(0.0..=1.0).contains(&threshold) {
+        return Err(AppError::InvalidArgument(
+            "Threshold must be between 0.0 and 1.0".to_string(),
+        ));
+    }
+    if !is_camera_running().await? {
+        return Err(AppError::CameraNotInitialized(
+            "Camera not running".to_string(),
+        ));
+    }
+    if is_recording().await? {
+        return Err("Microphone is in use by a recording".into());
+    }
+    if cpal::default_host().default_input_device().is_none() {
+        return Err(AppError::DeviceNotFound(
+            "No input device found".to_string(),
+        ));
+    }
+
+    STOP_SOUND_TRIGGER.store(false, Ordering::SeqCst);
+    SOUND_TRIGGER_RUNNING.store(true, Ordering::SeqCst);
+
+    thread::spawn(move || {
+        run_sound_trigger(app, threshold, Duration::from_millis(cooldown_ms));
+    });
+
+    Ok("Sound-triggered capture started".to_string())
+}
+
+/// Stop sound-triggered capture
+#[tauri::command]
+pub async fn stop_sound_triggered_capture() -> Result<String, AppError> {
+    if !SOUND_TRIGGER_RUNNING.load(Ordering::SeqCst) {
+        return Ok("Sound-triggered capture not running".to_string());
+    }
+
+    STOP_SOUND_TRIGGER.store(true, Ordering::SeqCst);
+
+    // Wait for the listening thread to finish (with timeout)
+    let mut attempts = 0;
+    while SOUND_TRIGGER_RUNNING.load(Ordering::SeqCst) && attempts < 50 {
+        thread::sleep(Duration::from_millis(50));
+        attempts += 1;
+    }
+
+    if SOUND_TRIGGER_RUNNING.load(Ordering::SeqCst) {
+        return Err("Sound-triggered capture failed to stop in time".into());
+    }
+
+    Ok("Sound-triggered capture stopped".to_string())
+}
+
+/// Listening loop: tracks the input peak and captures when it crosses the threshold
+fn run_sound_trigger(app: AppHandle, threshold: f32, cooldown: Duration) {
+    // Loudest sample since the last poll, stored as f32 bits
+    let peak = Arc::new(AtomicU32::new(0));
+
+    let stream = match build_level_stream(peak.clone()) {
+        Ok(s) => s,
+        Err(e) => {
+            let _ = app.emit("sound-trigger-error", e);
+            SOUND_TRIGGER_RUNNING.store(false, Ordering::SeqCst);
+            return;
+        }
+    };
+
+    let mut last_capture: Option<Instant> = None;
+    while !STOP_SOUND_TRIGGER.load(Ordering::SeqCst) {
+        thread::sleep(LEVEL_POLL_INTERVAL);
+
+        let level = f32::from_bits(peak.swap(0, Ordering::SeqCst));
+        let cooled_down = last_capture.is_none_or(|at| at.elapsed() >= cooldown);
+        if level < threshold || !cooled_down {
+            continue;
+        }
+        last_capture = Some(Instant::now());
+
+        let saved = tauri::async_runtime::block_on(capture_photo(
+            app.clone(),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        ));
+        match saved {
+            Ok(photo) if photo.success => {
+                let _ = app.emit(
+                    "sound-triggered-capture",
+                    SoundTriggeredCapture {
+                        path: photo.path,
+                        level,
+                    },
+                );
+            }
+            Ok(photo) => {
+                eprintln!(
+                    "Sound-triggered capture failed: {}",
+                    photo.error.unwrap_or_default()
+                );
+            }
+            Err(e) => eprintln!("Sound-triggered capture failed: {}", e),
+        }
+    }
+
+    drop(stream);
+    SOUND_TRIGGER_RUNNING.store(false, Ordering::SeqCst);
+    STOP_SOUND_TRIGGER.store(false, Ordering::SeqCst);
+}
+
+/// Open the default input and keep the running peak in `peak`
+fn build_level_stream(peak: Arc<AtomicU32>) -> Result<cpal::Stream, String> {
+    let device = cpal::default_host()
+        .default_input_device()
+        .ok_or("No input device found")?;
+    let config = choose_input_config(&device, None)?;
+
+    // i16/u16-only devices are converted to f32 like recordings
+    let stream = build_f32_input_stream(&device, &config, move |data| {
+        let buffer_peak = data.iter().fold(0.0f32, |max, s| max.max(s.abs()));
+        // Non-negative floats order the same as their bit patterns
+        peak.fetch_max(buffer_peak.to_bits(), Ordering::SeqCst);
+    })?;
+
+    stream
+        .play()
+        .map_err(|e| format!("Failed to start stream: {}", e))?;
+    Ok(stream)
+}
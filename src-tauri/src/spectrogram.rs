@@ -0,0 +1,148 @@
+//! Spectrogram images for recordings
+//!
+//! Each column is a Hann-windowed FFT centred on that point of the recording
+//! (channels mixed to mono), with low frequencies at the bottom and magnitude
+//! mapped through a dark-to-bright colour ramp. Images go in the same cache as
+//! the waveform thumbnails, keyed by size and FFT size.
+
+use crate::error::AppError;
+use crate::recorder::is_in_recordings_dir;
+use crate::wav::read_wav;
+use crate::waveform::{load_or_render, thumbnail_path, MAX_WAVEFORM_SIZE};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use image::{ImageFormat, Rgb, RgbImage};
+use rustfft::{num_complex::Complex, FftPlanner};
+use std::f32::consts::PI;
+use std::io::Cursor;
+use std::path::Path;
+
+const DEFAULT_FFT_SIZE: usize = 1024;
+const MIN_FFT_SIZE: usize = 64;
+const MAX_FFT_SIZE: usize = 8192;
+
+// Magnitudes are shown from this level (black) up to full scale (white)
+const MIN_DB: f32 = -90.0;
+
+// Colour ramp from silence to full scale
+const COLOR_RAMP: [[f32; 3]; 5] = [
+    [0.0, 0.0, 0.0],
+    [72.0, 20.0, 120.0],
+    [200.0, 40.0, 70.0],
+    [255.0, 193.0, 7.0],
+    [255.0, 255.0, 255.0],
+];
+
+/// Render (or load the cached) spectrogram PNG for a recording as a base64 data URL
+/// `fft_size` (a power of two, 64-8192, default 1024) trades time for frequency detail.
+#[tauri::command]
+pub async fn generate_spectrogram(
+    path: String,
+    width: u32,
+    height: u32,
+    fft_size: Option<usize>,
+) -> Result<String, AppError> {
+    if width == 0 || height == 0 || width > MAX_WAVEFORM_SIZE || height > MAX_WAVEFORM_SIZE {
+        return Err(AppError::InvalidArgument(format!(
+            "Spectrogram size must be between 1 and {} pixels",
+            MAX_WAVEFORM_SIZE
+        )));
+    }
+
+    let fft_size = fft_size.unwrap_or(DEFAULT_FFT_SIZE);
+    if !fft_size.is_power_of_two() || !(MIN_FFT_SIZE..=MAX_FFT_SIZE).contains(&fft_size) {
+        return Err(AppError::InvalidArgument(format!(
+            "FFT size must be a power of two between {} and {}",
+            MIN_FFT_SIZE, MAX_FFT_SIZE
+        )));
+    }
+
+    let source = Path::new(&path).to_path_buf();
+    if !is_in_recordings_dir(&source)? {
+        return Err(AppError::PathOutsideAllowedDir(
+            "Cannot read files outside recordings directory".to_string(),
+        ));
+    }
+
+    let cache_path = thumbnail_path(&source, &format!("{}x{}_fft{}", width, height, fft_size))?;
+
+    // FFTs across a long recording take a while; keep them off the async runtime
+    let png = tauri::async_runtime::spawn_blocking(move || {
+        load_or_render(&source, &cache_path, || {
+            render_spectrogram(&source, width, height, fft_size)
+        })
+    })
+    .await
+    .map_err(|e| format!("Failed to render spectrogram: {}", e))??;
+
+    Ok(format!("data:image/png;base64,{}", STANDARD.encode(&png)))
+}
+
+/// Draw one windowed FFT per column of a WAV into a PNG
+fn render_spectrogram(
+    source: &Path,
+    width: u32,
+    height: u32,
+    fft_size: usize,
+) -> Result<Vec<u8>, String> {
+    let wav = read_wav(source)?;
+    let channels = wav.channels as usize;
+    let mono: Vec<f32> = wav
+        .samples
+        .chunks_exact(channels)
+        .map(|frame| frame.iter().sum::<f32>() / channels as f32)
+        .collect();
+
+    let fft = FftPlanner::<f32>::new().plan_fft_forward(fft_size);
+    let window: Vec<f32> = (0..fft_size)
+        .map(|i| 0.5 - 0.5 * (2.0 * PI * i as f32 / fft_size as f32).cos())
+        .collect();
+    // Full-scale sine through a Hann window peaks at a quarter of the FFT size
+    let full_scale = fft_size as f32 / 4.0;
+    let bins = fft_size / 2;
+
+    let mut img = RgbImage::new(width, height);
+    let mut buffer = vec![Complex::new(0.0f32, 0.0); fft_size];
+
+    for x in 0..width {
+        // Centre the window on this column, zero-padding past either end
+        let centre = mono.len() * (2 * x as usize + 1) / (2 * width as usize);
+        let start = centre as isize - (fft_size / 2) as isize;
+        for (i, value) in buffer.iter_mut().enumerate() {
+            let sample = usize::try_from(start + i as isize)
+                .ok()
+                .and_then(|idx| mono.get(idx))
+                .copied()
+                .unwrap_or(0.0);
+            *value = Complex::new(sample * window[i], 0.0);
+        }
+        fft.process(&mut buffer);
+
+        for y in 0..height {
+            // Bins covered by this row, highest frequencies at the top
+            let row = (height - 1 - y) as usize;
+            let first = bins * row / height as usize;
+            let last = (bins * (row + 1) / height as usize).max(first + 1);
+            let magnitude = buffer[first..last]
+                .iter()
+                .fold(0.0f32, |max, c| max.max(c.norm()));
+
+            let db = 20.0 * (magnitude / full_scale).max(1e-9).log10();
+            let level = ((db - MIN_DB) / -MIN_DB).clamp(0.0, 1.0);
+            img.put_pixel(x, y, ramp_color(level));
+        }
+    }
+
+    let mut png = Cursor::new(Vec::new());
+    img.write_to(&mut png, ImageFormat::Png)
+        .map_err(|e| format!("Failed to encode PNG: {}", e))?;
+    Ok(png.into_inner())
+}
+
+/// Interpolate the colour ramp at `level` (0.0-1.0)
+fn ramp_color(level: f32) -> Rgb<u8> {
+    let scaled = level * (COLOR_RAMP.len() - 1) as f32;
+    let index = (scaled as usize).min(COLOR_RAMP.len() - 2);
+    let t = scaled - index as f32;
+    let (from, to) = (COLOR_RAMP[index], COLOR_RAMP[index + 1]);
+    Rgb([0, 1, 2].map(|c| (from[c] + (to[c] - from[c]) * t).round() as u8))
+}
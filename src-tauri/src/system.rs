@@ -6,6 +6,7 @@
 // Volume: Uses PipeWire (wpctl) -> PulseAudio (pactl) -> ALSA (amixer) fallback chain
 // Only controls speaker OUTPUT volume, never touches microphone/input
 
+use crate::error::AppError;
 use std::process::Command;
 
 /// Audio backend detection
@@ -113,12 +114,12 @@ fn get_brightness_current_kde() -> Result<i32, String> {
 
 /// Get current display brightness as percentage (0-100)
 #[tauri::command]
-pub fn get_brightness() -> Result<u8, String> {
+pub fn get_brightness() -> Result<u8, AppError> {
     let max = get_brightness_max_kde()?;
     let current = get_brightness_current_kde()?;
 
     if max <= 0 {
-        return Err("Invalid max brightness value".to_string());
+        return Err("Invalid max brightness value".into());
     }
 
     let percentage = ((current as f64 / max as f64) * 100.0).round() as u8;
@@ -127,7 +128,7 @@ pub fn get_brightness() -> Result<u8, String> {
 
 /// Set display brightness (percentage 0-100)
 #[tauri::command]
-pub fn set_brightness(level: u8) -> Result<(), String> {
+pub fn set_brightness(level: u8) -> Result<(), AppError> {
     // Clamp to safe range (never fully black, minimum 5%)
     let safe_level = level.clamp(5, 100);
 
@@ -152,7 +153,8 @@ pub fn set_brightness(level: u8) -> Result<(), String> {
         return Err(format!(
             "setBrightness failed: {}",
             String::from_utf8_lossy(&output.stderr)
-        ));
+        )
+        .into());
     }
 
     Ok(())
@@ -164,12 +166,13 @@ pub fn set_brightness(level: u8) -> Result<(), String> {
 
 /// Get current speaker volume as percentage (0-100)
 #[tauri::command]
-pub fn get_volume() -> Result<u8, String> {
-    match detect_audio_backend() {
+pub fn get_volume() -> Result<u8, AppError> {
+    let volume = match detect_audio_backend() {
         AudioBackend::PipeWire => get_volume_pipewire(),
         AudioBackend::PulseAudio => get_volume_pulseaudio(),
         AudioBackend::Alsa => get_volume_alsa(),
-    }
+    }?;
+    Ok(volume)
 }
 
 fn get_volume_pipewire() -> Result<u8, String> {
@@ -245,14 +248,15 @@ fn get_volume_alsa() -> Result<u8, String> {
 /// Set speaker volume (percentage 0-100)
 /// NOTE: This only affects OUTPUT volume, never touches microphone/input
 #[tauri::command]
-pub fn set_volume(level: u8) -> Result<(), String> {
+pub fn set_volume(level: u8) -> Result<(), AppError> {
     let safe_level = level.clamp(0, 100);
 
     match detect_audio_backend() {
         AudioBackend::PipeWire => set_volume_pipewire(safe_level),
         AudioBackend::PulseAudio => set_volume_pulseaudio(safe_level),
         AudioBackend::Alsa => set_volume_alsa(safe_level),
-    }
+    }?;
+    Ok(())
 }
 
 fn set_volume_pipewire(level: u8) -> Result<(), String> {
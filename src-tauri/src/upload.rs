@@ -0,0 +1,178 @@
+//! HTTP uploads of captured media to a backend server
+//!
+//! Files are sent as a single multipart/form-data field; the HTTP status code is
+//! returned as-is so the caller decides what counts as success.
+
+use crate::camera::{encode_jpeg, latest_frame_rgb};
+use crate::error::AppError;
+use crate::recorder::is_in_recordings_dir;
+use futures_util::{stream, TryStreamExt};
+use reqwest::{
+    header::{HeaderMap, HeaderName, HeaderValue},
+    multipart::{Form, Part},
+    Body,
+};
+use std::{collections::HashMap, path::Path, time::Duration};
+use tauri::{AppHandle, Emitter};
+use tokio::io::AsyncReadExt;
+
+// Used when the caller doesn't pass a timeout; applies to connecting and to each read,
+// not to the whole upload, so large files aren't cut off while they're still moving
+const DEFAULT_UPLOAD_TIMEOUT_MS: u64 = 30_000;
+
+// Files are sent in chunks of this size; progress is reported for files of at least
+// UPLOAD_PROGRESS_MIN_BYTES, at most once per percent
+const UPLOAD_CHUNK_SIZE: usize = 64 * 1024;
+const UPLOAD_PROGRESS_MIN_BYTES: u64 = 1024 * 1024;
+
+/// Upload progress event payload
+#[derive(Clone, serde::Serialize)]
+pub struct UploadProgress {
+    pub path: String,
+    pub bytes_sent: u64,
+    pub total_bytes: u64,
+}
+
+/// Capture the current frame and POST it as JPEG to `url` under `field_name`
+/// Returns the HTTP status code of the response.
+#[tauri::command]
+pub async fn upload_frame(
+    url: String,
+    field_name: String,
+    timeout_ms: Option<u64>,
+) -> Result<u16, AppError> {
+    let frame = latest_frame_rgb()?;
+    let jpeg = encode_jpeg(&frame)?;
+
+    let part = Part::bytes(jpeg)
+        .file_name("frame.jpg")
+        .mime_str("image/jpeg")
+        .map_err(|e| format!("Failed to build upload: {}", e))?;
+
+    Ok(post_part(&url, field_name, part, None, timeout_ms).await?)
+}
+
+/// POST a recording to `url` under `field_name`, returning the HTTP status code
+/// `headers` are added to the request, e.g. {"Authorization": "Bearer <token>"}.
+/// Large files emit `upload-progress` while they are sent.
+/// The file is streamed from disk; `timeout_ms` limits connecting and each wait for the
+/// server, not the whole upload.
+#[tauri::command]
+pub async fn upload_recording(
+    app: AppHandle,
+    path: String,
+    url: String,
+    field_name: String,
+    headers: Option<HashMap<String, String>>,
+    timeout_ms: Option<u64>,
+) -> Result<u16, AppError> {
+    let source = Path::new(&path);
+    if !is_in_recordings_dir(source)? {
+        return Err(AppError::PathOutsideAllowedDir(
+            "Cannot upload files outside recordings directory".to_string(),
+        ));
+    }
+
+    let file = tokio::fs::File::open(source)
+        .await
+        .map_err(|e| AppError::IoError(format!("Failed to read recording: {}", e)))?;
+    let total = file
+        .metadata()
+        .await
+        .map_err(|e| AppError::IoError(format!("Failed to read recording: {}", e)))?
+        .len();
+    let filename = source
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "recording.wav".to_string());
+
+    // Read the file chunk by chunk as the connection takes it, so progress follows what
+    // has been handed over; capped at the length announced in the request
+    let chunks = stream::try_unfold(file.take(total), |mut file| async move {
+        let mut chunk = vec![0u8; UPLOAD_CHUNK_SIZE];
+        let read = file.read(&mut chunk).await?;
+        if read == 0 {
+            return Ok::<_, std::io::Error>(None);
+        }
+        chunk.truncate(read);
+        Ok(Some((chunk, file)))
+    });
+    let mut last_percent = None;
+    let mut sent = 0;
+    let body = chunks.map_ok(move |chunk| {
+        sent += chunk.len() as u64;
+        let percent = sent * 100 / total.max(1);
+        if total >= UPLOAD_PROGRESS_MIN_BYTES && last_percent != Some(percent) {
+            last_percent = Some(percent);
+            let _ = app.emit(
+                "upload-progress",
+                UploadProgress {
+                    path: path.clone(),
+                    bytes_sent: sent,
+                    total_bytes: total,
+                },
+            );
+        }
+        chunk
+    });
+
+    let part = Part::stream_with_length(Body::wrap_stream(body), total)
+        .file_name(filename)
+        .mime_str("audio/wav")
+        .map_err(|e| format!("Failed to build upload: {}", e))?;
+
+    Ok(post_part(&url, field_name, part, headers.as_ref(), timeout_ms).await?)
+}
+
+/// POST a single multipart field and return the response status
+async fn post_part(
+    url: &str,
+    field_name: String,
+    part: Part,
+    headers: Option<&HashMap<String, String>>,
+    timeout_ms: Option<u64>,
+) -> Result<u16, String> {
+    if field_name.is_empty() {
+        return Err("Upload field name must not be empty".to_string());
+    }
+
+    let mut header_map = HeaderMap::new();
+    for (name, value) in headers.into_iter().flatten() {
+        let name = HeaderName::from_bytes(name.as_bytes())
+            .map_err(|e| format!("Invalid header name {:?}: {}", name, e))?;
+        let value = HeaderValue::from_str(value)
+            .map_err(|e| format!("Invalid value for header {}: {}", name, e))?;
+        header_map.insert(name, value);
+    }
+
+    let timeout_ms = timeout_ms.unwrap_or(DEFAULT_UPLOAD_TIMEOUT_MS);
+    let timeout = Duration::from_millis(timeout_ms);
+    let client = reqwest::Client::builder()
+        .connect_timeout(timeout)
+        .read_timeout(timeout)
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+
+    let response = client
+        .post(url)
+        .headers(header_map)
+        .multipart(Form::new().part(field_name, part))
+        .send()
+        .await
+        .map_err(|e| describe_request_error(url, timeout_ms, e))?;
+
+    Ok(response.status().as_u16())
+}
+
+/// Turn a reqwest error into a message that says what went wrong
+fn describe_request_error(url: &str, timeout_ms: u64, e: reqwest::Error) -> String {
+    if e.is_timeout() {
+        format!("Upload to {} timed out after {} ms", url, timeout_ms)
+    } else if e.is_connect() {
+        format!("Failed to connect to {}: {}", url, e)
+    } else if e.is_builder() {
+        format!("Invalid upload URL {}: {}", url, e)
+    } else {
+        format!("Failed to upload to {}: {}", url, e)
+    }
+}
@@ -0,0 +1,664 @@
+//! Minimal WAV encoding/decoding for recordings
+//!
+//! Writes 16-bit PCM or 32-bit float and reads the PCM/float variants our
+//! recorder and common tools produce. Samples are exchanged as interleaved f32 in -1.0..1.0.
+
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Cursor, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+const FORMAT_PCM: u16 = 1;
+const FORMAT_IEEE_FLOAT: u16 = 3;
+const FORMAT_EXTENSIBLE: u16 = 0xFFFE;
+// WAVE_FORMAT_EXTENSIBLE is the largest fmt chunk the PCM/float formats use
+const MAX_FMT_CHUNK_SIZE: u64 = 40;
+
+// Tail of the KSDATAFORMAT_SUBTYPE_* GUIDs; the first two bytes are the format code
+const SUBFORMAT_GUID_TAIL: [u8; 14] = [
+    0x00, 0x00, 0x00, 0x00, 0x10, 0x00, 0x80, 0x00, 0x00, 0xAA, 0x00, 0x38, 0x9B, 0x71,
+];
+
+/// Sample encoding for WAV files we write
+#[derive(Clone, Copy, Default, PartialEq, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WavSampleFormat {
+    /// 16-bit integer PCM
+    #[default]
+    Pcm16,
+    /// 32-bit IEEE float, written without quantization
+    Float32,
+}
+
+/// Decoded WAV audio
+pub struct WavData {
+    pub samples: Vec<f32>, // interleaved
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub sample_format: WavSampleFormat, // closest format we can write back
+}
+
+impl WavData {
+    /// Length of the audio in milliseconds
+    pub fn duration_ms(&self) -> u64 {
+        samples_to_ms(self.samples.len(), self.sample_rate, self.channels)
+    }
+}
+
+/// Convert an interleaved sample count to milliseconds
+pub fn samples_to_ms(num_samples: usize, sample_rate: u32, channels: u16) -> u64 {
+    if sample_rate == 0 || channels == 0 {
+        return 0;
+    }
+    (num_samples as u64 * 1000) / (sample_rate as u64 * channels as u64)
+}
+
+/// Convert milliseconds to an interleaved sample offset (always on a frame boundary)
+pub fn ms_to_samples(ms: u64, sample_rate: u32, channels: u16) -> usize {
+    let frames = ms * sample_rate as u64 / 1000;
+    (frames * channels as u64) as usize
+}
+
+/// Encode interleaved f32 samples as a WAV file (16-bit PCM or 32-bit float)
+pub fn encode_wav(
+    samples: &[f32],
+    sample_rate: u32,
+    channels: u16,
+    sample_format: WavSampleFormat,
+) -> Result<Vec<u8>, String> {
+    let data_size = samples.len() as u32 * sample_format.bytes_per_sample();
+
+    let mut buf = Cursor::new(Vec::new());
+    write_header(&mut buf, sample_rate, channels, sample_format, data_size)
+        .map_err(|e| e.to_string())?;
+    write_samples(&mut buf, samples, sample_format).map_err(|e| e.to_string())?;
+
+    Ok(buf.into_inner())
+}
+
+/// WAV file written incrementally, for recordings too long to buffer in memory
+/// The header carries maximum sizes until `finish` patches in the real ones,
+/// so a file cut off by a crash still reads back up to where it stopped.
+pub struct WavWriter {
+    file: BufWriter<File>,
+    sample_rate: u32,
+    channels: u16,
+    sample_format: WavSampleFormat,
+    samples_written: u64,
+}
+
+impl WavWriter {
+    /// Create `path` and write a placeholder header
+    pub fn create(
+        path: &Path,
+        sample_rate: u32,
+        channels: u16,
+        sample_format: WavSampleFormat,
+    ) -> Result<Self, String> {
+        let file = File::create(path).map_err(|e| format!("Failed to create WAV file: {}", e))?;
+        let mut file = BufWriter::new(file);
+        write_header(&mut file, sample_rate, channels, sample_format, u32::MAX)
+            .map_err(|e| format!("Failed to write WAV header: {}", e))?;
+
+        Ok(WavWriter {
+            file,
+            sample_rate,
+            channels,
+            sample_format,
+            samples_written: 0,
+        })
+    }
+
+    /// Append interleaved samples
+    pub fn write_samples(&mut self, samples: &[f32]) -> Result<(), String> {
+        write_samples(&mut self.file, samples, self.sample_format)
+            .map_err(|e| format!("Failed to write WAV data: {}", e))?;
+        self.samples_written += samples.len() as u64;
+        Ok(())
+    }
+
+    /// Length written so far in milliseconds
+    pub fn duration_ms(&self) -> u64 {
+        samples_to_ms(
+            self.samples_written as usize,
+            self.sample_rate,
+            self.channels,
+        )
+    }
+
+    /// Flush and patch the header sizes; returns the duration in milliseconds
+    pub fn finish(mut self) -> Result<u64, String> {
+        let data_size = (self.samples_written * self.sample_format.bytes_per_sample() as u64)
+            .min(u32::MAX as u64) as u32;
+
+        self.file
+            .seek(SeekFrom::Start(0))
+            .and_then(|_| {
+                write_header(
+                    &mut self.file,
+                    self.sample_rate,
+                    self.channels,
+                    self.sample_format,
+                    data_size,
+                )
+            })
+            .and_then(|_| self.file.flush())
+            .map_err(|e| format!("Failed to finalize WAV file: {}", e))?;
+
+        Ok(self.duration_ms())
+    }
+}
+
+impl WavSampleFormat {
+    fn bytes_per_sample(self) -> u32 {
+        match self {
+            WavSampleFormat::Pcm16 => 2,
+            WavSampleFormat::Float32 => 4,
+        }
+    }
+}
+
+/// Write the RIFF/fmt(/fact)/data headers for `data_size` bytes of samples
+/// More than two channels use WAVE_FORMAT_EXTENSIBLE so players know the speaker layout.
+fn write_header<W: Write>(
+    w: &mut W,
+    sample_rate: u32,
+    channels: u16,
+    sample_format: WavSampleFormat,
+    data_size: u32,
+) -> io::Result<()> {
+    let (audio_format, bits_per_sample) = match sample_format {
+        WavSampleFormat::Pcm16 => (FORMAT_PCM, 16u16),
+        WavSampleFormat::Float32 => (FORMAT_IEEE_FLOAT, 32u16),
+    };
+    let is_float = sample_format == WavSampleFormat::Float32;
+    let is_extensible = channels > 2;
+    let byte_rate = sample_rate * channels as u32 * (bits_per_sample as u32 / 8);
+    let block_align = channels * (bits_per_sample / 8);
+
+    // Non-PCM formats carry a cbSize field and a fact chunk with the frame count;
+    // the extensible format adds 22 bytes of layout after cbSize
+    let fmt_size: u32 = match (is_extensible, is_float) {
+        (true, _) => 40,
+        (false, true) => 18,
+        (false, false) => 16,
+    };
+    let fact_size: u32 = if is_float { 12 } else { 0 };
+    let riff_size = (4 + (8 + fmt_size) + fact_size + 8).saturating_add(data_size);
+
+    // RIFF header
+    w.write_all(b"RIFF")?;
+    w.write_all(&riff_size.to_le_bytes())?;
+    w.write_all(b"WAVE")?;
+
+    // fmt chunk
+    w.write_all(b"fmt ")?;
+    w.write_all(&fmt_size.to_le_bytes())?;
+    let format_tag = if is_extensible {
+        FORMAT_EXTENSIBLE
+    } else {
+        audio_format
+    };
+    w.write_all(&format_tag.to_le_bytes())?;
+    w.write_all(&channels.to_le_bytes())?;
+    w.write_all(&sample_rate.to_le_bytes())?;
+    w.write_all(&byte_rate.to_le_bytes())?;
+    w.write_all(&block_align.to_le_bytes())?;
+    w.write_all(&bits_per_sample.to_le_bytes())?;
+
+    if is_extensible {
+        // cbSize, valid bits, speaker mask, then the real format as a sub-format GUID
+        w.write_all(&22u16.to_le_bytes())?;
+        w.write_all(&bits_per_sample.to_le_bytes())?;
+        w.write_all(&channel_mask(channels).to_le_bytes())?;
+        w.write_all(&audio_format.to_le_bytes())?;
+        w.write_all(&SUBFORMAT_GUID_TAIL)?;
+    } else if is_float {
+        w.write_all(&0u16.to_le_bytes())?;
+    }
+
+    if is_float {
+        // fact chunk
+        let frames = data_size / block_align.max(1) as u32;
+        w.write_all(b"fact")?;
+        w.write_all(&4u32.to_le_bytes())?;
+        w.write_all(&frames.to_le_bytes())?;
+    }
+
+    // data chunk
+    w.write_all(b"data")?;
+    w.write_all(&data_size.to_le_bytes())
+}
+
+/// Standard speaker positions for a channel count (0 leaves the layout unspecified)
+fn channel_mask(channels: u16) -> u32 {
+    const FRONT_LEFT: u32 = 0x1;
+    const FRONT_RIGHT: u32 = 0x2;
+    const FRONT_CENTER: u32 = 0x4;
+    const LOW_FREQUENCY: u32 = 0x8;
+    const BACK_LEFT: u32 = 0x10;
+    const BACK_RIGHT: u32 = 0x20;
+    const BACK_CENTER: u32 = 0x100;
+    const SIDE_LEFT: u32 = 0x200;
+    const SIDE_RIGHT: u32 = 0x400;
+
+    let surround_5_1 =
+        FRONT_LEFT | FRONT_RIGHT | FRONT_CENTER | LOW_FREQUENCY | BACK_LEFT | BACK_RIGHT;
+    match channels {
+        1 => FRONT_CENTER,
+        2 => FRONT_LEFT | FRONT_RIGHT,
+        3 => FRONT_LEFT | FRONT_RIGHT | FRONT_CENTER,
+        4 => FRONT_LEFT | FRONT_RIGHT | BACK_LEFT | BACK_RIGHT,
+        5 => FRONT_LEFT | FRONT_RIGHT | FRONT_CENTER | BACK_LEFT | BACK_RIGHT,
+        6 => surround_5_1,
+        7 => surround_5_1 | BACK_CENTER,
+        8 => surround_5_1 | SIDE_LEFT | SIDE_RIGHT,
+        _ => 0,
+    }
+}
+
+/// Write samples in the given encoding
+fn write_samples<W: Write>(
+    w: &mut W,
+    samples: &[f32],
+    sample_format: WavSampleFormat,
+) -> io::Result<()> {
+    match sample_format {
+        // Convert f32 samples to i16
+        WavSampleFormat::Pcm16 => {
+            for &sample in samples {
+                let clamped = sample.clamp(-1.0, 1.0);
+                let val = (clamped * 32767.0) as i16;
+                w.write_all(&val.to_le_bytes())?;
+            }
+        }
+        // Write f32 samples as-is
+        WavSampleFormat::Float32 => {
+            for &sample in samples {
+                w.write_all(&sample.to_le_bytes())?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Read and decode a WAV file from disk
+pub fn read_wav(path: &Path) -> Result<WavData, String> {
+    let bytes = fs::read(path).map_err(|e| format!("Failed to read WAV file: {}", e))?;
+    decode_wav(&bytes)
+}
+
+/// Decode WAV bytes (16/24/32-bit PCM, 8-bit unsigned PCM or 32-bit float)
+pub fn decode_wav(bytes: &[u8]) -> Result<WavData, String> {
+    if bytes.len() < 12 || &bytes[0..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+        return Err("Not a WAV file".to_string());
+    }
+
+    let mut format: Option<(u16, u16, u32, u16)> = None; // (format, channels, rate, bits)
+    let mut data: Option<&[u8]> = None;
+
+    // Walk the chunks; tolerate a data chunk that runs past the end of the file
+    let mut pos = 12;
+    while pos + 8 <= bytes.len() {
+        let id = &bytes[pos..pos + 4];
+        let size = read_u32(bytes, pos + 4) as usize;
+        let body_start = pos + 8;
+        let body_end = body_start.saturating_add(size).min(bytes.len());
+        let body = &bytes[body_start..body_end];
+
+        match id {
+            b"fmt " if body.len() >= 16 => {
+                let mut audio_format = read_u16(body, 0);
+                // WAVE_FORMAT_EXTENSIBLE stores the real format in the sub-format GUID
+                if audio_format == FORMAT_EXTENSIBLE && body.len() >= 26 {
+                    audio_format = read_u16(body, 24);
+                }
+                format = Some((
+                    audio_format,
+                    read_u16(body, 2),
+                    read_u32(body, 4),
+                    read_u16(body, 14),
+                ));
+            }
+            b"data" => data = Some(body),
+            _ => {}
+        }
+
+        // Chunks are padded to an even size
+        pos = body_start.saturating_add(size).saturating_add(size & 1);
+    }
+
+    let (audio_format, channels, sample_rate, bits) = format.ok_or("WAV file has no fmt chunk")?;
+    let data = data.ok_or("WAV file has no data chunk")?;
+
+    if channels == 0 || sample_rate == 0 {
+        return Err("WAV file has an invalid format".to_string());
+    }
+
+    let samples: Vec<f32> = match (audio_format, bits) {
+        (FORMAT_PCM, 8) => data.iter().map(|&b| (b as f32 - 128.0) / 128.0).collect(),
+        (FORMAT_PCM, 16) => data
+            .chunks_exact(2)
+            .map(|b| i16::from_le_bytes([b[0], b[1]]) as f32 / 32768.0)
+            .collect(),
+        (FORMAT_PCM, 24) => data
+            .chunks_exact(3)
+            .map(|b| (i32::from_le_bytes([0, b[0], b[1], b[2]]) >> 8) as f32 / 8_388_608.0)
+            .collect(),
+        (FORMAT_PCM, 32) => data
+            .chunks_exact(4)
+            .map(|b| i32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f32 / 2_147_483_648.0)
+            .collect(),
+        (FORMAT_IEEE_FLOAT, 32) => data
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect(),
+        _ => {
+            return Err(format!(
+                "Unsupported WAV encoding (format {}, {} bits)",
+                audio_format, bits
+            ))
+        }
+    };
+
+    // Drop a trailing partial frame
+    let usable = samples.len() - samples.len() % channels as usize;
+    let mut samples = samples;
+    samples.truncate(usable);
+
+    let sample_format = if audio_format == FORMAT_IEEE_FLOAT {
+        WavSampleFormat::Float32
+    } else {
+        WavSampleFormat::Pcm16
+    };
+
+    Ok(WavData {
+        samples,
+        sample_rate,
+        channels,
+        sample_format,
+    })
+}
+
+/// Read a WAV's duration from its header without loading the samples
+/// Returns None for files that aren't WAVs or have no usable fmt/data chunk.
+pub fn read_wav_duration_ms(path: &Path) -> Option<u64> {
+    let mut file = File::open(path).ok()?;
+    let file_len = file.metadata().ok()?.len();
+
+    let mut riff = [0u8; 12];
+    file.read_exact(&mut riff).ok()?;
+    if &riff[0..4] != b"RIFF" || &riff[8..12] != b"WAVE" {
+        return None;
+    }
+
+    let mut byte_rate: Option<u32> = None;
+    let mut pos: u64 = 12;
+
+    loop {
+        let mut header = [0u8; 8];
+        file.read_exact(&mut header).ok()?;
+        let size = read_u32(&header, 4) as u64;
+
+        match &header[0..4] {
+            b"fmt " => {
+                let mut fmt = [0u8; 16];
+                file.read_exact(&mut fmt).ok()?;
+                byte_rate = Some(read_u32(&fmt, 8));
+            }
+            b"data" => {
+                let byte_rate = byte_rate.filter(|&r| r > 0)? as u64;
+                // A truncated file holds less data than the header claims
+                let available = file_len.saturating_sub(pos + 8);
+                return Some(size.min(available) * 1000 / byte_rate);
+            }
+            _ => {}
+        }
+
+        pos += 8 + size + (size & 1);
+        file.seek(SeekFrom::Start(pos)).ok()?;
+    }
+}
+
+/// Copy `start_ms..end_ms` of a WAV file into a new, self-contained WAV
+/// Only the requested span is read from disk and the original encoding is kept.
+pub fn read_wav_range(path: &Path, start_ms: u64, end_ms: u64) -> Result<Vec<u8>, String> {
+    if start_ms >= end_ms {
+        return Err(format!("Invalid range {}..{} ms", start_ms, end_ms));
+    }
+
+    let mut file = File::open(path).map_err(|e| format!("Failed to read WAV file: {}", e))?;
+    let file_len = file
+        .metadata()
+        .map_err(|e| format!("Failed to read WAV file: {}", e))?
+        .len();
+
+    let mut riff = [0u8; 12];
+    file.read_exact(&mut riff)
+        .map_err(|e| format!("Failed to read WAV file: {}", e))?;
+    if &riff[0..4] != b"RIFF" || &riff[8..12] != b"WAVE" {
+        return Err("Not a WAV file".to_string());
+    }
+
+    // Find the fmt chunk (kept verbatim) and where the data starts
+    let mut fmt_chunk: Option<Vec<u8>> = None;
+    let mut pos: u64 = 12;
+    let (data_start, data_size) = loop {
+        let mut header = [0u8; 8];
+        file.read_exact(&mut header)
+            .map_err(|_| "WAV file has no data chunk".to_string())?;
+        let size = read_u32(&header, 4) as u64;
+
+        match &header[0..4] {
+            b"fmt " => {
+                if size > MAX_FMT_CHUNK_SIZE {
+                    return Err("WAV file has an unsupported format".to_string());
+                }
+                let mut body = vec![0u8; size as usize];
+                file.read_exact(&mut body)
+                    .map_err(|e| format!("Failed to read WAV file: {}", e))?;
+                fmt_chunk = Some(body);
+            }
+            // A truncated file holds less data than the header claims
+            b"data" => break (pos + 8, size.min(file_len.saturating_sub(pos + 8))),
+            _ => {}
+        }
+
+        pos += 8 + size + (size & 1);
+        file.seek(SeekFrom::Start(pos))
+            .map_err(|e| format!("Failed to read WAV file: {}", e))?;
+    };
+
+    let fmt = fmt_chunk
+        .filter(|f| f.len() >= 16)
+        .ok_or("WAV file has no fmt chunk")?;
+    let sample_rate = read_u32(&fmt, 4) as u64;
+    let block_align = read_u16(&fmt, 12) as u64;
+    if sample_rate == 0 || block_align == 0 {
+        return Err("WAV file has an invalid format".to_string());
+    }
+
+    let total_frames = data_size / block_align;
+    let start_frame = start_ms.saturating_mul(sample_rate) / 1000;
+    let end_frame = (end_ms.saturating_mul(sample_rate) / 1000).min(total_frames);
+    if start_frame >= end_frame {
+        return Err(format!(
+            "Range {}..{} ms is outside the {} ms recording",
+            start_ms,
+            end_ms,
+            total_frames * 1000 / sample_rate
+        ));
+    }
+
+    let span = ((end_frame - start_frame) * block_align).min(u32::MAX as u64) as u32;
+    let mut data = vec![0u8; span as usize];
+    file.seek(SeekFrom::Start(data_start + start_frame * block_align))
+        .and_then(|_| file.read_exact(&mut data))
+        .map_err(|e| format!("Failed to read WAV file: {}", e))?;
+
+    // Non-PCM formats need a fact chunk with the frame count
+    let mut audio_format = read_u16(&fmt, 0);
+    if audio_format == FORMAT_EXTENSIBLE && fmt.len() >= 26 {
+        audio_format = read_u16(&fmt, 24);
+    }
+    let is_pcm = audio_format == FORMAT_PCM;
+    let fact_size: u32 = if is_pcm { 0 } else { 12 };
+    let fmt_padded = fmt.len() as u32 + (fmt.len() as u32 & 1);
+    let riff_size = (4 + 8 + fmt_padded + fact_size + 8).saturating_add(span + (span & 1));
+
+    let mut out = Vec::with_capacity(riff_size as usize + 8);
+    out.extend_from_slice(b"RIFF");
+    out.extend_from_slice(&riff_size.to_le_bytes());
+    out.extend_from_slice(b"WAVE");
+    out.extend_from_slice(b"fmt ");
+    out.extend_from_slice(&(fmt.len() as u32).to_le_bytes());
+    out.extend_from_slice(&fmt);
+    if fmt.len() % 2 == 1 {
+        out.push(0);
+    }
+    if !is_pcm {
+        out.extend_from_slice(b"fact");
+        out.extend_from_slice(&4u32.to_le_bytes());
+        out.extend_from_slice(&((end_frame - start_frame) as u32).to_le_bytes());
+    }
+    out.extend_from_slice(b"data");
+    out.extend_from_slice(&span.to_le_bytes());
+    out.extend_from_slice(&data);
+    if span % 2 == 1 {
+        out.push(0);
+    }
+    Ok(out)
+}
+
+/// Fix the RIFF, data and fact sizes of a WAV whose header doesn't match its length
+/// (e.g. a recording interrupted before its header was finalized). Returns true if
+/// the file was changed.
+pub fn repair_wav_header(path: &Path) -> Result<bool, String> {
+    let mut bytes = fs::read(path).map_err(|e| format!("Failed to read WAV file: {}", e))?;
+    if bytes.len() < 12 || &bytes[0..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+        return Err("Not a WAV file".to_string());
+    }
+
+    let mut block_align: Option<usize> = None;
+    let mut fact_pos: Option<usize> = None;
+    let mut data_pos: Option<usize> = None;
+
+    // Walk the chunks up to the data chunk, which runs to the end of what was written
+    let mut pos = 12;
+    while pos + 8 <= bytes.len() {
+        let size = read_u32(&bytes, pos + 4) as usize;
+        match &bytes[pos..pos + 4] {
+            b"fmt " if pos + 8 + 16 <= bytes.len() => {
+                block_align = Some(read_u16(&bytes, pos + 8 + 12) as usize);
+            }
+            b"fact" if pos + 8 + 4 <= bytes.len() => fact_pos = Some(pos),
+            b"data" => {
+                data_pos = Some(pos);
+                break;
+            }
+            _ => {}
+        }
+        pos = pos.saturating_add(8 + size + (size & 1));
+    }
+
+    let block_align = block_align
+        .filter(|&b| b > 0)
+        .ok_or("WAV file has no usable fmt chunk")?;
+    let data_pos = data_pos.ok_or("WAV file has no data chunk")?;
+    let data_start = data_pos + 8;
+
+    // A data size that fits in the file is kept (with any chunks after it);
+    // otherwise the data is whatever whole frames were actually written
+    let declared = read_u32(&bytes, data_pos + 4) as usize;
+    let available = bytes.len() - data_start;
+    let truncated = declared > available;
+    if truncated {
+        let data_size = (available - available % block_align).min(u32::MAX as usize - data_start);
+        bytes.truncate(data_start + data_size);
+        // Chunks are padded to an even size
+        if data_size % 2 == 1 {
+            bytes.push(0);
+        }
+
+        bytes[data_pos + 4..data_start].copy_from_slice(&(data_size as u32).to_le_bytes());
+        if let Some(fact) = fact_pos {
+            let frames = (data_size / block_align) as u32;
+            bytes[fact + 8..fact + 12].copy_from_slice(&frames.to_le_bytes());
+        }
+    }
+
+    let riff_size = (bytes.len() - 8).min(u32::MAX as usize) as u32;
+    if !truncated && read_u32(&bytes, 4) == riff_size {
+        return Ok(false);
+    }
+    bytes[4..8].copy_from_slice(&riff_size.to_le_bytes());
+
+    fs::write(path, &bytes).map_err(|e| format!("Failed to write WAV file: {}", e))?;
+    Ok(true)
+}
+
+fn read_u16(bytes: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([bytes[offset], bytes[offset + 1]])
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([
+        bytes[offset],
+        bytes[offset + 1],
+        bytes[offset + 2],
+        bytes[offset + 3],
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn float32_wav_round_trips() {
+        let samples = [0.0, 0.5, -0.25, 1.0, -1.0, 0.123_456_78];
+        let bytes = encode_wav(&samples, 48000, 2, WavSampleFormat::Float32).unwrap();
+
+        assert_eq!(&bytes[12..16], b"fmt ");
+        assert_eq!(read_u16(&bytes, 20), FORMAT_IEEE_FLOAT);
+        assert_eq!(read_u16(&bytes, 34), 32);
+
+        let decoded = decode_wav(&bytes).unwrap();
+        assert_eq!(decoded.sample_rate, 48000);
+        assert_eq!(decoded.channels, 2);
+        assert!(decoded.sample_format == WavSampleFormat::Float32);
+        assert_eq!(decoded.samples, samples);
+    }
+
+    #[test]
+    fn four_channel_header_is_extensible() {
+        for (sample_format, audio_format, bits) in [
+            (WavSampleFormat::Pcm16, FORMAT_PCM, 16),
+            (WavSampleFormat::Float32, FORMAT_IEEE_FLOAT, 32),
+        ] {
+            let mut header = Vec::new();
+            write_header(&mut header, 48000, 4, sample_format, 0).unwrap();
+
+            assert_eq!(read_u32(&header, 16), 40);
+            assert_eq!(read_u16(&header, 20), FORMAT_EXTENSIBLE);
+            assert_eq!(read_u16(&header, 22), 4);
+            assert_eq!(read_u16(&header, 36), 22);
+            assert_eq!(read_u16(&header, 38), bits);
+            assert_eq!(read_u32(&header, 40), 0x33);
+            assert_eq!(read_u16(&header, 44), audio_format);
+            assert_eq!(header[46..60], SUBFORMAT_GUID_TAIL);
+        }
+    }
+
+    #[test]
+    fn read_wav_range_handles_huge_offsets() {
+        let path = std::env::temp_dir().join(format!("honeybee-range-{}.wav", std::process::id()));
+        let samples = vec![0.5; 4800];
+        let bytes = encode_wav(&samples, 48000, 1, WavSampleFormat::Pcm16).unwrap();
+        fs::write(&path, bytes).unwrap();
+
+        let clip = read_wav_range(&path, 50, u64::MAX).unwrap();
+        assert_eq!(decode_wav(&clip).unwrap().samples.len(), 2400);
+        assert!(read_wav_range(&path, u64::MAX - 1, u64::MAX).is_err());
+        fs::remove_file(&path).unwrap();
+    }
+}
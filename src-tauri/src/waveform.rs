@@ -0,0 +1,175 @@
+//! Static waveform thumbnails for recordings
+//!
+//! PNGs are cached in a `.thumbnails` folder inside the recordings directory,
+//! keyed by recording name and pixel size, and regenerated when the recording is
+//! newer than its cached image. Spectrograms share the same cache.
+
+use crate::error::AppError;
+use crate::recorder::is_in_recordings_dir;
+use crate::wav::read_wav;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use image::{ImageFormat, Rgba, RgbaImage};
+use std::fs;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+
+const THUMBNAILS_DIR: &str = ".thumbnails";
+
+// Largest thumbnail we'll render in either dimension
+pub(crate) const MAX_WAVEFORM_SIZE: u32 = 4096;
+
+const WAVEFORM_COLOR: Rgba<u8> = Rgba([255, 193, 7, 255]);
+const BACKGROUND_COLOR: Rgba<u8> = Rgba([0, 0, 0, 0]);
+
+/// Render (or load the cached) waveform PNG for a recording as a base64 data URL
+#[tauri::command]
+pub async fn generate_waveform_image(
+    path: String,
+    width: u32,
+    height: u32,
+) -> Result<String, AppError> {
+    if width == 0 || height == 0 || width > MAX_WAVEFORM_SIZE || height > MAX_WAVEFORM_SIZE {
+        return Err(AppError::InvalidArgument(format!(
+            "Waveform size must be between 1 and {} pixels",
+            MAX_WAVEFORM_SIZE
+        )));
+    }
+
+    let source = Path::new(&path);
+    if !is_in_recordings_dir(source)? {
+        return Err(AppError::PathOutsideAllowedDir(
+            "Cannot read files outside recordings directory".to_string(),
+        ));
+    }
+
+    let cache_path = thumbnail_path(source, &format!("{}x{}", width, height))?;
+    let png = load_or_render(source, &cache_path, || {
+        render_waveform(source, width, height)
+    })?;
+
+    Ok(format!("data:image/png;base64,{}", STANDARD.encode(&png)))
+}
+
+/// Cache location for a recording's thumbnail, named "<stem>_<key>.png"
+pub(crate) fn thumbnail_path(source: &Path, key: &str) -> Result<PathBuf, String> {
+    let dir = source.parent().ok_or("Recording has no parent directory")?;
+    let stem = source
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .ok_or("Recording has no file name")?;
+    Ok(dir
+        .join(THUMBNAILS_DIR)
+        .join(format!("{}_{}.png", stem, key)))
+}
+
+/// Read the cached PNG if it's fresh, otherwise render and cache it
+pub(crate) fn load_or_render(
+    source: &Path,
+    cache_path: &Path,
+    render: impl FnOnce() -> Result<Vec<u8>, String>,
+) -> Result<Vec<u8>, String> {
+    if is_cache_fresh(source, cache_path) {
+        return fs::read(cache_path).map_err(|e| format!("Failed to read thumbnail cache: {}", e));
+    }
+
+    let png = render()?;
+
+    // A failed cache write shouldn't stop the thumbnail from showing
+    if let Some(dir) = cache_path.parent() {
+        if let Err(e) = fs::create_dir_all(dir).and_then(|_| fs::write(cache_path, &png)) {
+            eprintln!("Failed to cache thumbnail {:?}: {}", cache_path, e);
+        }
+    }
+    Ok(png)
+}
+
+/// Delete every cached waveform and spectrogram for a recording
+pub(crate) fn remove_cached_thumbnails(source: &Path) -> Result<(), String> {
+    let (Some(dir), Some(stem)) = (source.parent(), source.file_stem()) else {
+        return Ok(());
+    };
+    let cache_dir = dir.join(THUMBNAILS_DIR);
+    if !cache_dir.is_dir() {
+        return Ok(());
+    }
+
+    let prefix = format!("{}_", stem.to_string_lossy());
+    let entries =
+        fs::read_dir(&cache_dir).map_err(|e| format!("Failed to read thumbnail cache: {}", e))?;
+
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        // Only "<stem>_<w>x<h>.png" or "<stem>_<w>x<h>_fft<n>.png", so
+        // "<stem>_trim_..." of another recording is kept
+        let is_ours = name
+            .strip_prefix(&prefix)
+            .and_then(|rest| rest.strip_suffix(".png"))
+            .is_some_and(is_thumbnail_key);
+        if is_ours {
+            fs::remove_file(entry.path())
+                .map_err(|e| format!("Failed to delete cached thumbnail: {}", e))?;
+        }
+    }
+    Ok(())
+}
+
+/// True for "<w>x<h>" and "<w>x<h>_fft<n>" cache keys
+fn is_thumbnail_key(key: &str) -> bool {
+    let is_number = |s: &str| s.parse::<u32>().is_ok();
+    let size = match key.split_once("_fft") {
+        Some((size, fft_size)) if is_number(fft_size) => size,
+        Some(_) => return false,
+        None => key,
+    };
+    size.split_once('x')
+        .is_some_and(|(w, h)| is_number(w) && is_number(h))
+}
+
+/// True if the cached image exists and is at least as new as the recording
+fn is_cache_fresh(source: &Path, cache_path: &Path) -> bool {
+    let modified = |p: &Path| fs::metadata(p).and_then(|m| m.modified()).ok();
+    match (modified(source), modified(cache_path)) {
+        (Some(source_time), Some(cache_time)) => cache_time >= source_time,
+        _ => false,
+    }
+}
+
+/// Draw per-column peak amplitudes of a WAV into a PNG
+fn render_waveform(source: &Path, width: u32, height: u32) -> Result<Vec<u8>, String> {
+    let wav = read_wav(source)?;
+    let channels = wav.channels as usize;
+    let frames = wav.samples.len() / channels;
+
+    let mut img = RgbaImage::from_pixel(width, height, BACKGROUND_COLOR);
+    let center = (height - 1) as f32 / 2.0;
+
+    for x in 0..width {
+        // Frames covered by this column
+        let start = frames * x as usize / width as usize;
+        let end = (frames * (x as usize + 1) / width as usize)
+            .max(start + 1)
+            .min(frames);
+        if start >= end {
+            continue;
+        }
+
+        // Peak across all channels in the column
+        let peak = wav.samples[start * channels..end * channels]
+            .iter()
+            .fold(0.0f32, |max, s| max.max(s.abs()))
+            .min(1.0);
+
+        // Always draw at least the centre line so silence is still visible
+        let half = (peak * center).round();
+        let top = (center - half).floor().max(0.0) as u32;
+        let bottom = (center + half).ceil().min((height - 1) as f32) as u32;
+        for y in top..=bottom {
+            img.put_pixel(x, y, WAVEFORM_COLOR);
+        }
+    }
+
+    let mut png = Cursor::new(Vec::new());
+    img.write_to(&mut png, ImageFormat::Png)
+        .map_err(|e| format!("Failed to encode PNG: {}", e))?;
+    Ok(png.into_inner())
+}
@@ -10,7 +10,7 @@ import {
 } from "lucide-react";
 import { invoke } from "@tauri-apps/api/core";
 import { listen, UnlistenFn } from "@tauri-apps/api/event";
-import { cn } from "@/lib/utils";
+import { cn, errorMessage } from "@/lib/utils";
 import { Button } from "@/components/ui/button";
 
 interface CameraAppProps {
@@ -18,9 +18,11 @@ interface CameraAppProps {
 }
 
 interface CameraFrame {
-  data: string; // base64 data URL
+  data: string; // base64 data URL (jpeg) or raw base64 bytes (yuyv)
   width: number;
   height: number;
+  format: "jpeg" | "yuyv";
+  stream: "color" | "infrared";
 }
 
 interface CameraError {
@@ -29,6 +31,8 @@ interface CameraError {
 
 interface PhotoSaved {
   path: string;
+  width: number;
+  height: number;
   success: boolean;
   error: string | null;
 }
@@ -107,7 +111,7 @@ export function CameraApp({ onClose }: CameraAppProps) {
       setIsStreaming(true);
     } catch (err) {
       console.error("Failed to start camera:", err);
-      setError(err instanceof Error ? err.message : String(err));
+      setError(errorMessage(err));
     } finally {
       setIsLoading(false);
     }
@@ -134,7 +138,7 @@ export function CameraApp({ onClose }: CameraAppProps) {
       // Result will come through the photo-saved event
     } catch (err) {
       console.error("Failed to capture photo:", err);
-      setError(err instanceof Error ? err.message : String(err));
+      setError(errorMessage(err));
       setIsCapturing(false);
     }
   }, []);
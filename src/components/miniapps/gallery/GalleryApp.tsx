@@ -20,6 +20,9 @@ interface GalleryImage {
   path: string;
   size: number;
   modified: number;
+  extension: string;
+  favorite: boolean;
+  tags: string[];
 }
 
 export function GalleryApp({ onClose }: GalleryAppProps) {
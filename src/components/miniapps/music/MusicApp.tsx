@@ -21,6 +21,7 @@ interface RecordingInfo {
   path: string;
   size: number;
   modified: number;
+  encrypted: boolean;
 }
 
 export function MusicApp({ onClose }: MusicAppProps) {
@@ -76,7 +77,10 @@ export function MusicApp({ onClose }: MusicAppProps) {
       }
 
       try {
-        const dataUrl = await invoke<string>("read_audio_file", {
+        const command = recording.encrypted
+          ? "read_encrypted_recording"
+          : "read_audio_file";
+        const dataUrl = await invoke<string>(command, {
           path: recording.path,
         });
 
@@ -8,7 +8,7 @@ import {
 } from "lucide-react";
 import { invoke } from "@tauri-apps/api/core";
 import { listen, UnlistenFn } from "@tauri-apps/api/event";
-import { cn } from "@/lib/utils";
+import { cn, errorMessage } from "@/lib/utils";
 import { Button } from "@/components/ui/button";
 
 interface RecorderAppProps {
@@ -92,7 +92,7 @@ export function RecorderApp({ onClose }: RecorderAppProps) {
       await invoke("start_recording");
       setIsRecording(true);
     } catch (err) {
-      setError(String(err));
+      setError(errorMessage(err));
     }
   }, []);
 
@@ -102,7 +102,7 @@ export function RecorderApp({ onClose }: RecorderAppProps) {
       await invoke("stop_recording");
       setIsRecording(false);
     } catch (err) {
-      setError(String(err));
+      setError(errorMessage(err));
       setSaving(false);
     }
   }, []);
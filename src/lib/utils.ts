@@ -3,4 +3,17 @@ import { twMerge } from "tailwind-merge"
 
 export function cn(...inputs: ClassValue[]) {
   return twMerge(clsx(inputs))
-}
\ No newline at end of file
+}
+
+// Error from a failed Tauri command (AppError in src-tauri/src/error.rs)
+export interface AppError {
+  code: string
+  message: string
+}
+
+export function errorMessage(err: unknown): string {
+  if (typeof err === "object" && err !== null && "message" in err) {
+    return String((err as AppError).message)
+  }
+  return String(err)
+}